@@ -0,0 +1,26 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ilex_xml::{parse, Item};
+
+fn find_children(c: &mut Criterion) {
+    let mut xml = String::from("<root>");
+    for i in 0..5000 {
+        if i % 10 == 0 {
+            xml.push_str("<wanted/>");
+        } else {
+            xml.push_str("<other>text</other>");
+        }
+    }
+    xml.push_str("</root>");
+
+    let items = parse(&xml).unwrap();
+    let Item::Element(root) = &items[0] else {
+        panic!("Benchmark data is corrupt.");
+    };
+
+    c.bench_function("find_children 5000", |b| {
+        b.iter(|| root.find_children(black_box("wanted")).count())
+    });
+}
+
+criterion_group!(benches, find_children);
+criterion_main!(benches);
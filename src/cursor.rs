@@ -0,0 +1,83 @@
+use crate::{Element, Item};
+
+/** A position within the children of an element.
+
+Holds the parent element and the index of a child, so the child can be edited in place
+without searching for it again.
+If the parent's children are changed through [`Cursor::parent`] so that the index is out of bounds,
+[`Cursor::get`], [`Cursor::get_mut`] and [`Cursor::replace`] return `None`.
+
+```rust
+# use ilex_xml::*;
+let xml = "<a><b/><c/></a>";
+
+let Item::Element(element) = &mut parse(xml)?[0] else {
+    panic!();
+};
+
+let cursor = element
+    .find_cursor(|item| matches!(item, Item::Element(el) if el.get_name().unwrap() == "c"))
+    .unwrap();
+
+cursor.replace(vec![Item::new_text("x"), Item::new_text("y")]);
+
+assert_eq!(element.to_string(), "<a><b/>xy</a>");
+# Ok::<(), Error>(())
+```*/
+#[derive(Debug)]
+pub struct Cursor<'c, 'a> {
+    parent: &'c mut Element<'a>,
+    index: usize,
+}
+
+impl<'c, 'a> Cursor<'c, 'a> {
+    /** Create a cursor pointing at a child of the parent.
+
+    Returns `None` if the index is out of bounds.*/
+    pub fn new(parent: &'c mut Element<'a>, index: usize) -> Option<Self> {
+        if index < parent.children.len() {
+            Some(Cursor { parent, index })
+        } else {
+            None
+        }
+    }
+
+    /** Get the index of the item within its parent's children. */
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /** Get the parent of the item. */
+    pub fn parent(&mut self) -> &mut Element<'a> {
+        self.parent
+    }
+
+    /** Get the item the cursor points at.
+
+    Returns `None` if the index is no longer in bounds.*/
+    pub fn get(&self) -> Option<&Item<'a>> {
+        self.parent.children.get(self.index)
+    }
+
+    /** Get the item the cursor points at.
+
+    Returns `None` if the index is no longer in bounds.*/
+    pub fn get_mut(&mut self) -> Option<&mut Item<'a>> {
+        self.parent.children.get_mut(self.index)
+    }
+
+    /** Replace the item with the given items and return the replaced item.
+
+    The items are inserted at the position of the replaced item, in order.
+    An empty list removes the item.
+    If the index is no longer in bounds, nothing is changed and `None` is returned.*/
+    pub fn replace(self, items: Vec<Item<'a>>) -> Option<Item<'a>> {
+        if self.index >= self.parent.children.len() {
+            return None;
+        }
+        self.parent
+            .children
+            .splice(self.index..=self.index, items)
+            .next()
+    }
+}
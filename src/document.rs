@@ -0,0 +1,108 @@
+use std::{fmt::Display, str::FromStr};
+
+use quick_xml::events::Event;
+
+use crate::{
+    items_to_string, parse,
+    parsing::{find_root, parse_reader},
+    DocumentError, Element, GetEvents, Item,
+};
+
+/** A complete XML document.
+
+Holds all top level items, validated like in [`parse_document`](crate::parse_document),
+so there is exactly one root element.
+
+A document can be created from a `&str`, borrowing from it, or from a `String` or with `parse`,
+owning its data.
+
+```rust
+# use ilex_xml::*;
+let xml = String::from(r#"<?xml version="1.0"?><root><child/></root>"#);
+
+let document = Document::try_from(xml)?;
+
+assert_eq!(document.root().get_name().unwrap(), "root");
+assert_eq!(document.to_string(), r#"<?xml version="1.0"?><root><child/></root>"#);
+
+let result: Result<Document, _> = "<a/><b/>".parse();
+assert!(matches!(result, Err(DocumentError::MultipleRoots)));
+# Ok::<(), DocumentError>(())
+```*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document<'a> {
+    items: Vec<Item<'a>>,
+    root: usize,
+}
+
+impl<'a> Document<'a> {
+    /** Create a document from its top level items.
+
+    Fails if the items are not a valid document.*/
+    pub fn new(items: Vec<Item<'a>>) -> Result<Self, DocumentError> {
+        let root = find_root(&items)?;
+        Ok(Document { items, root })
+    }
+
+    /** Get the root element. */
+    pub fn root(&self) -> &Element<'a> {
+        let Item::Element(root) = &self.items[self.root] else {
+            unreachable!();
+        };
+        root
+    }
+
+    /** Get the root element. */
+    pub fn root_mut(&mut self) -> &mut Element<'a> {
+        let Item::Element(root) = &mut self.items[self.root] else {
+            unreachable!();
+        };
+        root
+    }
+
+    /** Get all top level items, including the root element. */
+    pub fn items(&self) -> &[Item<'a>] {
+        &self.items
+    }
+
+    /** Take the top level items, including the root element. */
+    pub fn into_items(self) -> Vec<Item<'a>> {
+        self.items
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Document<'a> {
+    type Error = DocumentError;
+
+    fn try_from(xml: &'a str) -> Result<Self, Self::Error> {
+        Document::new(parse(xml)?)
+    }
+}
+
+impl TryFrom<String> for Document<'static> {
+    type Error = DocumentError;
+
+    fn try_from(xml: String) -> Result<Self, Self::Error> {
+        Document::new(parse_reader(xml.as_bytes())?)
+    }
+}
+
+impl FromStr for Document<'static> {
+    type Err = DocumentError;
+
+    fn from_str(xml: &str) -> Result<Self, Self::Err> {
+        Document::new(parse_reader(xml.as_bytes())?)
+    }
+}
+
+impl GetEvents for Document<'_> {
+    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event<'_>> + '_> {
+        Box::new(self.items.iter().flat_map(|item| item.get_all_events()))
+    }
+}
+
+impl Display for Document<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", items_to_string(&self.items))
+    }
+}
@@ -1,35 +1,223 @@
-use std::{collections::HashMap, fmt::Display, io::Cursor, num::NonZero, string::FromUtf8Error};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+    hash::{DefaultHasher, Hash, Hasher},
+    num::{NonZero, ParseFloatError, ParseIntError},
+    str::FromStr,
+};
 
 use quick_xml::{
+    escape::{escape, unescape},
     events::{attributes::Attribute, BytesStart, Event},
-    Writer,
+    name::QName,
 };
 
 use crate::{
-    util::{qname_to_string, u8_to_string, GetEvents, ToStringSafe},
-    Error, Item,
+    parse_reader,
+    serialize::{events_to_string, events_to_string_lossy},
+    util::{
+        attribute_spans, escape_quotes, is_xml_whitespace, qname_to_string, u8_to_string,
+        AttributeSpan, GetEvents, ToStringSafe,
+    },
+    Cursor, Error, Item, Other, SerializeOptions, Warning,
 };
 
-/** An XML element: ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```. */
+/** An error when getting an attribute as a specific type. */
+#[derive(Debug, Clone)]
+pub enum AttributeValueError<E> {
+    /** The attribute could not be read. */
+    Xml(Error),
+    /** The attribute is present, but its value could not be parsed. */
+    Parse(E),
+}
+
+impl<E: Display> Display for AttributeValueError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValueError::Xml(err) => write!(f, "could not read attribute: {err}"),
+            AttributeValueError::Parse(err) => write!(f, "could not parse attribute: {err}"),
+        }
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for AttributeValueError<E> {}
+
+/** The name, attributes and children of an element. See [`Element::into_parts`]. */
+pub type ElementParts<'a> = (String, Vec<(String, String)>, Vec<Item<'a>>);
+
+/** A step of [`Element::walk`]. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkEvent<'e, 'a> {
+    /** An element is entered, before its children. */
+    Enter(&'e Element<'a>),
+    /** An item which is not an element. */
+    Leaf(&'e Item<'a>),
+    /** An element is left, after its children. */
+    Exit(&'e Element<'a>),
+}
+
+/** An XML element: ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```.
+
+Elements are ordered by comparing, in this order:
+1. The tag name, bytewise.
+2. The attributes as they are stored, bytewise. This includes quotes and whitespace, so it is consistent with `==`.
+3. The children, lexicographically, see [`Item`].
+4. [`Element::self_closing`], with `false` first.*/
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element<'a> {
     pub(crate) element: BytesStart<'a>,
     /** All items contained within the element. */
     pub children: Vec<Item<'a>>,
-    /** If the element is childless: Should it be self-closing? */
+    /** If the element is childless: Should it be self-closing?
+
+    After parsing, this reflects the form used in the source precisely:
+    `true` for ```<x/>``` and `false` for ```<x></x>```.*/
     pub self_closing: bool,
 }
 
 impl<'a> Element<'a> {
     /** Create a new Element. */
-    pub fn new(name: &'a str, self_closing: bool) -> Self {
+    pub fn new(name: impl Into<Cow<'a, str>>, self_closing: bool) -> Self {
+        Element {
+            element: BytesStart::new(name),
+            children: Vec::new(),
+            self_closing,
+        }
+    }
+
+    /** Create a self-closing element with the given attributes, in the order given.
+
+    Attribute values are escaped, like in [`Element::set_attribute`].
+    Cheaper than calling [`Element::set_attribute`] repeatedly, since the attributes are only written once.
+
+    ```rust
+    # use ilex_xml::*;
+    let element = Element::with_attributes("rect", [("width", "10"), ("title", "a & b")]);
+
+    assert_eq!(element.to_string(), r#"<rect width="10" title="a &amp; b"/>"#);
+    ```*/
+    pub fn with_attributes<'b>(
+        name: impl Into<Cow<'a, str>>,
+        attributes: impl IntoIterator<Item = (&'b str, &'b str)>,
+    ) -> Self {
+        Element {
+            element: BytesStart::new(name).with_attributes(attributes),
+            children: Vec::new(),
+            self_closing: true,
+        }
+    }
+
+    /** Create an element holding the content as character data.
+
+    The content is not escaped but wrapped in CDATA sections, split wherever it contains `]]>`
+    like in [`Other::new_cdata_sections`], so any content is safe to embed.
+
+    ```rust
+    # use ilex_xml::*;
+    let element = Element::with_cdata("script", "if (a < b && c) {}");
+
+    assert_eq!(element.to_string(), "<script><![CDATA[if (a < b && c) {}]]></script>");
+    assert_eq!(element.get_character_data(), "if (a < b && c) {}");
+    ```*/
+    pub fn with_cdata(name: impl Into<Cow<'a, str>>, content: &'a str) -> Self {
         Element {
             element: BytesStart::new(name),
+            children: Item::new_cdata_sections(content),
+            self_closing: false,
+        }
+    }
+
+    /** Create an element without children from a quick_xml start tag.
+
+    Useful for bridging events from a custom quick_xml pipeline into the tree.
+
+    ```rust
+    # use ilex_xml::*;
+    # use quick_xml::events::BytesStart;
+    let start = BytesStart::new("a").with_attributes([("x", "1")]);
+
+    let element = Element::from_bytes_start(start, true);
+
+    assert_eq!(element.to_string(), r#"<a x="1"/>"#);
+    assert_eq!(element.into_bytes_start().name().as_ref(), b"a");
+    ```*/
+    pub fn from_bytes_start(start: BytesStart<'a>, self_closing: bool) -> Self {
+        Element {
+            element: start,
             children: Vec::new(),
             self_closing,
         }
     }
 
+    /** Take the quick_xml start tag holding the name and attributes of the element.
+
+    The children are dropped.*/
+    pub fn into_bytes_start(self) -> BytesStart<'a> {
+        self.element
+    }
+
+    /** Create an element from its name, attributes and children.
+
+    Attribute values are escaped, like in [`Element::set_attribute`].
+    If there are no children, the element is self-closing.*/
+    pub fn from_parts(
+        name: String,
+        attributes: Vec<(String, String)>,
+        children: Vec<Item<'a>>,
+    ) -> Self {
+        let mut element = BytesStart::new(name);
+        element.extend_attributes(
+            attributes
+                .iter()
+                .map(|(key, value)| Attribute::from((key.as_str(), value.as_str()))),
+        );
+        Element {
+            element,
+            self_closing: children.is_empty(),
+            children,
+        }
+    }
+
+    /** Split the element into its name, attributes and children.
+
+    Attribute values are unescaped and returned in source order.
+    Unlike most getters, this fails on malformed attributes instead of skipping them.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r#"<a x="1 &amp; 2"><b/></a>"#;
+
+    let Item::Element(element) = parse(xml)?.remove(0) else {
+        panic!();
+    };
+
+    let (name, attributes, children) = element.into_parts()?;
+
+    assert_eq!(name, "a");
+    assert_eq!(attributes, [(String::from("x"), String::from("1 & 2"))]);
+
+    let element = Element::from_parts(name, attributes, children);
+
+    assert_eq!(element.to_string(), xml);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn into_parts(self) -> Result<ElementParts<'a>, Error> {
+        let name = qname_to_string(&self.element.name())
+            .map_err(|err| Error::NonDecodable(Some(err.utf8_error())))?;
+
+        let mut attributes = Vec::new();
+        for attr in self.element.attributes() {
+            let attr = attr?;
+            let key = qname_to_string(&attr.key)
+                .map_err(|err| Error::NonDecodable(Some(err.utf8_error())))?;
+            attributes.push((key, attr.unescape_value()?.into_owned()));
+        }
+
+        Ok((name, attributes, self.children))
+    }
+
     /** Get all descendants matching the predicate.
     ```rust
     // Example of finding all elements with tag name "a":
@@ -50,10 +238,10 @@ impl<'a> Element<'a> {
     assert_eq!(a_elements.count(), 2);
     # Ok::<(), Error>(())
     ```*/
-    pub fn find_descendants(
-        &self,
-        predicate: &'a impl Fn(&Item) -> bool,
-    ) -> Box<dyn Iterator<Item = &Item> + '_> {
+    pub fn find_descendants<'s>(
+        &'s self,
+        predicate: &'s impl Fn(&Item) -> bool,
+    ) -> Box<dyn Iterator<Item = &'s Item<'a>> + 's> {
         // get direct children matching the predicate
         let matching_children = self.children.iter().filter(|item| predicate(item));
 
@@ -74,36 +262,247 @@ impl<'a> Element<'a> {
         Box::new(chain)
     }
 
+    /** Consume the element and iterate over all of its descendants, moving them out of the tree.
+
+    Descendants are yielded depth-first in document order, with every element before its children.
+    Since its children are yielded separately, every element is yielded without children.
+    The element itself is not included.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = parse("<a><b>1</b><c/></a>")?.remove(0) else {
+        panic!();
+    };
+
+    let descendants: Vec<String> = element.into_descendants().map(|item| item.to_string()).collect();
+
+    assert_eq!(descendants, ["<b></b>", "1", "<c/>"]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn into_descendants(self) -> impl Iterator<Item = Item<'a>> {
+        let mut stack = vec![self.children.into_iter()];
+
+        std::iter::from_fn(move || loop {
+            let Some(next) = stack.last_mut()?.next() else {
+                stack.pop();
+                continue;
+            };
+            let Item::Element(mut element) = next else {
+                return Some(next);
+            };
+            stack.push(std::mem::take(&mut element.children).into_iter());
+            return Some(Item::Element(element));
+        })
+    }
+
+    /** Walk over the element and all of its descendants in document order.
+
+    Every element is reported twice, when it is entered and when it is left, so the depth can be tracked
+    and traversals can be built without recursion. The element itself is entered first and left last.
+    The walk doesn't recurse internally either, so deep trees can't overflow the stack.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse("<a><b>1</b><!--2--></a>")?[0] else {
+        panic!();
+    };
+
+    let mut depth = 0;
+    let mut lines = Vec::new();
+    for event in element.walk() {
+        match event {
+            WalkEvent::Enter(element) => {
+                lines.push(format!("{depth} {}", element.get_name()?));
+                depth += 1;
+            }
+            WalkEvent::Leaf(item) => lines.push(format!("{depth} {item}")),
+            WalkEvent::Exit(_) => depth -= 1,
+        }
+    }
+
+    assert_eq!(lines, ["0 a", "1 b", "2 1", "1 <!--2-->"]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn walk(&self) -> impl Iterator<Item = WalkEvent<'_, 'a>> {
+        let mut start = Some(self);
+        let mut stack = Vec::new();
+
+        std::iter::from_fn(move || {
+            if let Some(element) = start.take() {
+                stack.push((element, element.children.iter()));
+                return Some(WalkEvent::Enter(element));
+            }
+
+            let (element, children) = stack.last_mut()?;
+            let element = *element;
+            match children.next() {
+                None => {
+                    stack.pop();
+                    Some(WalkEvent::Exit(element))
+                }
+                Some(Item::Element(child)) => {
+                    stack.push((child, child.children.iter()));
+                    Some(WalkEvent::Enter(child))
+                }
+                Some(item) => Some(WalkEvent::Leaf(item)),
+            }
+        })
+    }
+
     /** Find all child elements with matching name */
-    pub fn find_children(&'a self, name: &'a str) -> impl Iterator<Item = &Element> {
+    pub fn find_children(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element<'a>> {
         self.children
             .iter()
             .filter_map(|child| match child {
                 Item::Element(element) => Some(element),
                 _ => None,
             })
-            .filter(move |child| {
-                let child_name = child.get_name();
-                child_name.is_ok() && child_name.unwrap() == name
-            })
+            .filter(move |child| child.name_equals(name))
     }
 
     /** Find all child elements with matching name */
-    pub fn find_children_mut(&'a mut self, name: &'a str) -> impl Iterator<Item = &mut Element> {
+    pub fn find_children_mut(
+        &'a mut self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a mut Element<'a>> {
         self.children
             .iter_mut()
             .filter_map(|child| match child {
                 Item::Element(element) => Some(element),
                 _ => None,
             })
-            .filter(move |child| {
-                let child_name = child.get_name();
-                child_name.is_ok() && child_name.unwrap() == name
+            .filter(move |child| child.name_equals(name))
+    }
+
+    /** Get the first child element with matching name, appending a new self-closing one if there is none.
+
+    Like `HashMap::entry().or_insert`, this makes edits idempotent.
+
+    ```rust
+    # use ilex_xml::*;
+    let mut items = parse("<config><user/></config>")?;
+    let Item::Element(config) = &mut items[0] else {
+        panic!();
+    };
+
+    config.ensure_child("settings").set_attribute("theme", "dark");
+    config.ensure_child("settings").set_attribute("size", "2");
+
+    assert_eq!(
+        config.to_string(),
+        r#"<config><user/><settings theme="dark" size="2"/></config>"#
+    );
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn ensure_child(&mut self, name: &str) -> &mut Element<'a> {
+        let index = self
+            .children
+            .iter()
+            .position(|child| matches!(child, Item::Element(element) if element.name_equals(name)));
+        let index = index.unwrap_or_else(|| {
+            self.children
+                .push(Item::Element(Element::new(String::from(name), true)));
+            self.children.len() - 1
+        });
+        let Item::Element(element) = &mut self.children[index] else {
+            unreachable!();
+        };
+        element
+    }
+
+    /** Get the child element at the given position, counting only elements, starting at 0.
+
+    Text, comments and other items are skipped, so whitespace used for formatting doesn't shift the positions.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<row>\n  <a/>\n  <!-- b -->\n  <c/>\n</row>";
+
+    let Item::Element(row) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(row.nth_child_element(1).unwrap().get_name()?, "c");
+    assert!(row.nth_child_element(2).is_none());
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn nth_child_element(&self, n: usize) -> Option<&Element<'a>> {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                Item::Element(element) => Some(element),
+                _ => None,
+            })
+            .nth(n)
+    }
+
+    /** Get the child element at the given position, counting only elements, starting at 0.
+
+    See [`Element::nth_child_element`].*/
+    pub fn nth_child_element_mut(&mut self, n: usize) -> Option<&mut Element<'a>> {
+        self.children
+            .iter_mut()
+            .filter_map(|child| match child {
+                Item::Element(element) => Some(element),
+                _ => None,
             })
+            .nth(n)
+    }
+
+    /** Group the child elements by the value of an attribute.
+
+    Values are used as they are stored, like in [`Element::get_attribute`].
+    Children lacking the attribute are grouped under `missing`, or skipped if it is `None`.
+    Within each group, the children are in document order.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r#"<list><i type="a"/><i type="b"/><i/><i type="a"/></list>"#;
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    let groups = element.group_children_by_attribute("type", Some("none"));
+
+    assert_eq!(groups["a"].len(), 2);
+    assert_eq!(groups["b"].len(), 1);
+    assert_eq!(groups["none"].len(), 1);
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored, so children with a malformed attribute count as lacking it.*/
+    pub fn group_children_by_attribute(
+        &self,
+        key: &str,
+        missing: Option<&str>,
+    ) -> HashMap<String, Vec<&Element<'a>>> {
+        let mut groups: HashMap<String, Vec<&Element<'a>>> = HashMap::new();
+
+        for child in &self.children {
+            let Item::Element(element) = child else {
+                continue;
+            };
+            let group = match element.get_attribute(key) {
+                Ok(Some(value)) => value,
+                _ => match missing {
+                    Some(missing) => String::from(missing),
+                    None => continue,
+                },
+            };
+            groups.entry(group).or_default().push(element);
+        }
+
+        groups
     }
 
     /** Get all items at a certain depth within the element.
 
+    Every kind of item is returned at every depth: elements as well as text, comments and so on.
+    Only elements can contain other items, so the search descends through the elements at each level,
+    which reaches every item at the requested depth. Items are returned in document order.
+    Use [`Element::get_elements_at_depth`] to only get elements.
+
     ```xml
     <element>
         <item depth="1">
@@ -116,7 +515,7 @@ impl<'a> Element<'a> {
     pub fn get_items_at_depth(
         &self,
         depth: NonZero<usize>,
-    ) -> Box<dyn Iterator<Item = &Item> + '_> {
+    ) -> Box<dyn Iterator<Item = &Item<'a>> + '_> {
         if depth.get() == 1 {
             return Box::new(self.children.iter());
         }
@@ -137,9 +536,43 @@ impl<'a> Element<'a> {
         Box::new(items)
     }
 
+    /** Get all elements at a certain depth within the element.
+
+    Like [`Element::get_items_at_depth`], but all items other than elements are left out at every depth.
+
+    ```rust
+    # use ilex_xml::*;
+    # use std::num::NonZero;
+    let xml = "<a>text<b>more text<c/></b><!--comment--><d/></a>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_items_at_depth(NonZero::new(2).unwrap()).count(), 2);
+
+    let names: Vec<String> = element
+        .get_elements_at_depth(NonZero::new(1).unwrap())
+        .map(|el| el.get_name().unwrap())
+        .collect();
+    assert_eq!(names, ["b", "d"]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_elements_at_depth(
+        &self,
+        depth: NonZero<usize>,
+    ) -> impl Iterator<Item = &Element<'a>> + '_ {
+        self.get_items_at_depth(depth)
+            .filter_map(|item| match item {
+                Item::Element(element) => Some(element),
+                _ => None,
+            })
+    }
+
     /** Get all items at a certain depth within the element.
 
     Depth must not be zero.
+    Like [`Element::get_items_at_depth`], every kind of item is returned at every depth.
 
     ```xml
     <element>
@@ -153,7 +586,7 @@ impl<'a> Element<'a> {
     pub fn get_items_at_depth_mut(
         &'a mut self,
         depth: usize,
-    ) -> Box<dyn Iterator<Item = &mut Item> + '_> {
+    ) -> Box<dyn Iterator<Item = &'a mut Item<'a>> + 'a> {
         if depth == 1 {
             return Box::new(self.children.iter_mut());
         }
@@ -175,85 +608,1042 @@ impl<'a> Element<'a> {
         Box::new(items)
     }
 
-    /** Get the text content of all text items within the element.
+    /** Remove the child at the given index and return it.
 
-    ```xml
-    <element>Hello<child>World</child></element>
-    ```
+    Returns `None` if the index is out of bounds.
+    The child is moved out without cloning, so it can be grafted onto another element.*/
+    pub fn take_child(&mut self, index: usize) -> Option<Item<'a>> {
+        if index < self.children.len() {
+            Some(self.children.remove(index))
+        } else {
+            None
+        }
+    }
 
-    The above would result in "HelloWorld".
+    /** Append an item to the children.
 
-    Parsing errors are silently ignored.*/
-    pub fn get_text_content(&self) -> String {
-        self.children
-            .iter()
-            .filter_map(|child| match child {
-                Item::Text(text) => match text.get_value() {
-                    Ok(text) => Some(text),
-                    Err(_) => None,
-                },
-                Item::Element(element) => Some(element.get_text_content()),
-                _ => None,
-            })
-            .collect()
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<a><x/></a><b></b>";
+
+    let mut items = parse(xml)?;
+    let [Item::Element(a), Item::Element(b)] = &mut items[..] else {
+        panic!();
+    };
+
+    let x = a.take_child(0).unwrap();
+    b.graft(x);
+
+    assert_eq!(items_to_string(&items), "<a></a><b><x/></b>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn graft(&mut self, item: Item<'a>) {
+        self.children.push(item);
     }
 
-    /** Get all attributes.
+    /** Get a cursor pointing at the child at the given index.
 
-    Parsing errors are silently ignored.*/
-    pub fn get_all_attributes(&'a self) -> impl Iterator<Item = (String, String)> + 'a {
-        self.element
-            .attributes()
-            .filter_map(|attr| {
-                if attr.is_ok() {
-                    Some(attr.unwrap())
-                } else {
-                    None
-                }
-            })
-            .map(|attr| {
-                (
-                    qname_to_string(&attr.key),
-                    String::from_utf8((*attr.value).to_vec()),
-                )
-            })
-            .filter_map(|attr| {
-                if attr.0.is_err() || attr.1.is_err() {
-                    return None;
-                }
-                Some((attr.0.unwrap(), attr.1.unwrap()))
-            })
+    Returns `None` if the index is out of bounds.*/
+    pub fn cursor(&mut self, index: usize) -> Option<Cursor<'_, 'a>> {
+        Cursor::new(self, index)
     }
 
-    /** Get a map of all attributes.
+    /** Get a cursor pointing at the first descendant matching the predicate.
 
-    If an attribute occurs multiple times, the last occurence is used.
+    Descendants are searched in document order.*/
+    pub fn find_cursor(&mut self, predicate: impl Fn(&Item) -> bool) -> Option<Cursor<'_, 'a>> {
+        let path = self.find_with_path(&predicate)?;
+        let (index, parent_path) = path.split_last()?;
 
-    Parsing errors are silently ignored.*/
-    pub fn get_attributes(&self) -> HashMap<String, String> {
-        HashMap::from_iter(self.get_all_attributes())
+        let mut parent = self;
+        for i in parent_path {
+            let Item::Element(element) = &mut parent.children[*i] else {
+                unreachable!();
+            };
+            parent = element;
+        }
+
+        Cursor::new(parent, *index)
     }
 
-    /** Get an attribute. */
-    pub fn get_attribute(&self, key: &str) -> Result<Option<String>, Error> {
-        let Some(attr) = self.element.try_get_attribute(key)? else {
-            return Ok(None);
-        };
-        let value_res = u8_to_string(&attr.value);
-        if value_res.is_err() {
-            return Err(Error::NonDecodable(Some(
-                value_res.unwrap_err().utf8_error(),
-            )));
+    /** Get the indices leading to the first descendant matching the predicate.
+
+    The first index refers to a child of this element, the second to a child of that child, and so on.
+    Descendants are searched in document order.*/
+    pub fn find_with_path(&self, predicate: &impl Fn(&Item) -> bool) -> Option<Vec<usize>> {
+        for (index, child) in self.children.iter().enumerate() {
+            if predicate(child) {
+                return Some(vec![index]);
+            }
+            if let Item::Element(element) = child {
+                if let Some(mut path) = element.find_with_path(predicate) {
+                    path.insert(0, index);
+                    return Some(path);
+                }
+            }
         }
-        Ok(Some(value_res.unwrap()))
+        None
     }
 
-    /** Check if the element has the attribute. */
-    pub fn has_attribute(&self, key: &str) -> bool {
-        let Ok(result) = self.element.try_get_attribute(key) else {
-            return false;
-        };
-        return result.is_some();
+    /** Get the depth of the first element matching the predicate, relative to this element.
+
+    This element itself has depth 0, its children depth 1 and so on.
+    The predicate only sees elements, since this element has to be checked as well.
+    Returns `None` if no element matches.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse("<a><b><c/></b></a>")?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.depth_of(|el| el.name_equals("a")), Some(0));
+    assert_eq!(element.depth_of(|el| el.name_equals("c")), Some(2));
+    assert_eq!(element.depth_of(|el| el.name_equals("d")), None);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn depth_of(&self, predicate: impl Fn(&Element) -> bool) -> Option<usize> {
+        if predicate(self) {
+            return Some(0);
+        }
+        self.find_with_path(&|item| matches!(item, Item::Element(element) if predicate(element)))
+            .map(|path| path.len())
+    }
+
+    /** Format a path of child indices as a slash-delimited string, like `/root/body/section[2]/p`.
+
+    The path starts at this element. Elements are named by their tag name, other items by their
+    kind (`text()`, `comment()`, ...). A 1-based position is added where siblings share the same name.
+
+    Returns `None` if the path does not lead to an item.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<root><body><section/><section><p>hi</p></section></body></root>";
+
+    let Item::Element(root) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    let path = root.find_with_path(&|item| matches!(item, Item::Text(_))).unwrap();
+    assert_eq!(path, [0, 1, 0, 0]);
+
+    assert_eq!(
+        root.format_path(&path).unwrap(),
+        "/root/body/section[2]/p/text()"
+    );
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn format_path(&self, path: &[usize]) -> Option<String> {
+        let mut result = format!("/{}", String::from_utf8_lossy(self.element.name().as_ref()));
+        let mut parent = self;
+
+        for (depth, index) in path.iter().enumerate() {
+            let item = parent.children.get(*index)?;
+            let name = path_segment(item);
+
+            let mut position = 0;
+            let mut count = 0;
+            for (i, sibling) in parent.children.iter().enumerate() {
+                if path_segment(sibling) == name {
+                    count += 1;
+                    if i <= *index {
+                        position += 1;
+                    }
+                }
+            }
+
+            result.push('/');
+            result.push_str(&name);
+            if count > 1 {
+                result.push_str(&format!("[{position}]"));
+            }
+
+            match item {
+                Item::Element(element) => parent = element,
+                _ if depth + 1 == path.len() => (),
+                _ => return None,
+            }
+        }
+
+        Some(result)
+    }
+
+    /** Remove all children. */
+    pub fn clear_children(&mut self) {
+        self.children.clear();
+    }
+
+    /** Replace all children.
+
+    If the new children are not empty, the element is no longer marked as self-closing,
+    so it is written with an end tag even if it is emptied again later.
+    Otherwise [`Element::self_closing`] is left as it is.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(mut element) = parse("<list/>")?.remove(0) else {
+        panic!();
+    };
+
+    element.set_children(vec![Item::new_text("empty")]);
+    assert_eq!(element.to_string(), "<list>empty</list>");
+
+    element.set_children(Vec::new());
+    assert_eq!(element.to_string(), "<list></list>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn set_children(&mut self, children: Vec<Item<'a>>) {
+        if !children.is_empty() {
+            self.self_closing = false;
+        }
+        self.children = children;
+    }
+
+    /** Check if the element has no children at all, not even whitespace.
+
+    This is independent of [`Element::self_closing`], so ```<x/>``` and ```<x></x>``` are both empty,
+    while ```<x> </x>``` is not. Use it to decide whether an element can be collapsed to ```<x/>```
+    without changing its content. See [`Element::has_only_whitespace`] for pruning.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse("<a/><a></a><a> </a>")?;
+
+    let empty: Vec<bool> = items
+        .iter()
+        .map(|item| matches!(item, Item::Element(el) if el.is_empty()))
+        .collect();
+
+    assert_eq!(empty, [true, true, false]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /** Check if every child is text or CDATA made up of XML whitespace only, see [`Item::is_whitespace`].
+
+    This is true for empty elements as well as for ```<x> </x>```, but not if there is a comment,
+    a processing instruction or a child element, even an empty one.
+    Use it to find elements carrying no content, e.g. to prune them after trimming.
+    Nothing is serialized, and only text containing references is unescaped.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse("<a/><a>\n\t</a><a> <b/> </a><a><!-- note --></a>")?;
+
+    let blank: Vec<bool> = items
+        .iter()
+        .map(|item| matches!(item, Item::Element(el) if el.has_only_whitespace()))
+        .collect();
+
+    assert_eq!(blank, [true, true, false, false]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn has_only_whitespace(&self) -> bool {
+        self.children.iter().all(Item::is_whitespace)
+    }
+
+    /** Get the children between two markers, excluding the markers themselves.
+
+    The start marker is the first child matching `start`, the end marker the first child after it matching `end`.
+    Returns an empty slice if either marker is missing, which includes an end marker occurring only before the start marker.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<body><h1/><hr/><p>1</p><p>2</p><hr/><p>3</p></body>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    let is_hr = |item: &Item| matches!(item, Item::Element(el) if el.name_equals("hr"));
+
+    assert_eq!(items_to_string(element.children_between(is_hr, is_hr)), "<p>1</p><p>2</p>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn children_between(
+        &self,
+        start: impl Fn(&Item) -> bool,
+        end: impl Fn(&Item) -> bool,
+    ) -> &[Item<'a>] {
+        let Some(start) = self.children.iter().position(start) else {
+            return &[];
+        };
+        let rest = &self.children[start + 1..];
+        let Some(end) = rest.iter().position(end) else {
+            return &[];
+        };
+        &rest[..end]
+    }
+
+    /** Retain only the children matching the predicate.
+
+    Works like `Vec::retain`: the predicate is called once for every child, in order.*/
+    pub fn retain_children(&mut self, predicate: impl FnMut(&Item) -> bool) {
+        self.children.retain(predicate);
+    }
+
+    /** Retain only the descendants matching the predicate.
+
+    The predicate is applied to the children first, then recursively to the children of every remaining element.
+    Removing an element also removes all of its descendants.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r#"<a><b debug="true"><c/></b><d><e debug="true"/></d></a>"#;
+
+    let Item::Element(element) = &mut parse(xml)?[0] else {
+        panic!();
+    };
+
+    element.retain_descendants(|item| match item {
+        Item::Element(el) => !el.has_attribute("debug"),
+        _ => true,
+    });
+
+    assert_eq!(element.to_string(), "<a><d></d></a>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn retain_descendants(&mut self, mut predicate: impl FnMut(&Item) -> bool) {
+        self.retain_descendants_inner(&mut predicate);
+    }
+
+    fn retain_descendants_inner(&mut self, predicate: &mut impl FnMut(&Item) -> bool) {
+        self.children.retain(|item| predicate(item));
+
+        for child in self.children.iter_mut() {
+            if let Item::Element(element) = child {
+                element.retain_descendants_inner(predicate);
+            }
+        }
+    }
+
+    /** Remove all descendants matching the predicate and return how many were removed.
+
+    Works like [`Element::retain_descendants`] with the predicate negated: the children are checked first,
+    then the children of every remaining element, recursively.
+    Removing an element also removes all of its descendants. They are not checked and not counted.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r#"<body><div class="ad"><div class="ad"/></div><p>text<span class="ad"/></p></body>"#;
+
+    let Item::Element(element) = &mut parse(xml)?[0] else {
+        panic!();
+    };
+
+    let removed = element.remove_descendants(|item| match item {
+        Item::Element(el) => el.get_attribute("class").unwrap().as_deref() == Some("ad"),
+        _ => false,
+    });
+
+    assert_eq!(removed, 2);
+    assert_eq!(element.to_string(), "<body><p>text</p></body>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn remove_descendants(&mut self, mut predicate: impl FnMut(&Item) -> bool) -> usize {
+        let mut removed = 0;
+        self.retain_descendants(|item| {
+            let remove = predicate(item);
+            if remove {
+                removed += 1;
+            }
+            !remove
+        });
+        removed
+    }
+
+    /** Check if the element was written in the self-closing form ```<x/>```.
+
+    For a parsed element, this tells which form the source used, as long as `self_closing` hasn't been changed since.
+    An element that has children is never written self-closing, so this is `false` for it.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse("<a/><a></a>")?;
+
+    let [Item::Element(empty), Item::Element(expanded)] = &items[..] else {
+        panic!();
+    };
+
+    assert!(empty.was_self_closing());
+    assert!(!expanded.was_self_closing());
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn was_self_closing(&self) -> bool {
+        self.self_closing && self.children.is_empty()
+    }
+
+    /** Make every element without children self-closing, including this one.
+
+    Elements with children are written with separate start and end tags, no matter how `self_closing` was set before.
+
+    ```rust
+    # use ilex_xml::*;
+    let mut element = Element::new("a", false);
+    element.children.push(Item::new_element("b", false));
+
+    element.auto_self_close();
+
+    assert_eq!(element.to_string(), "<a><b/></a>");
+    ```*/
+    pub fn auto_self_close(&mut self) {
+        self.visit_elements_mut(true, |element| {
+            element.self_closing = element.children.is_empty();
+        });
+    }
+
+    /** Call a function on every element within the element, in document order.
+
+    If `include_self` is true, the function is called on the element itself first.
+    Elements are visited after their parent has been visited, so changes to a parent's children are reflected in the traversal.
+
+    The traversal is not recursive, so it is safe to use on deeply nested trees.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<A><B><C/></B></A>";
+
+    let Item::Element(element) = &mut parse(xml)?[0] else {
+        panic!();
+    };
+
+    element.visit_elements_mut(true, |el| {
+        let name = el.get_name().unwrap().to_lowercase();
+        el.set_name(&name);
+    });
+
+    assert_eq!(element.to_string(), "<a><b><c/></b></a>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn visit_elements_mut(&mut self, include_self: bool, mut f: impl FnMut(&mut Element<'a>)) {
+        let mut stack: Vec<&mut Element<'a>> = Vec::new();
+
+        if include_self {
+            stack.push(self);
+        } else {
+            stack.extend(
+                self.children
+                    .iter_mut()
+                    .rev()
+                    .filter_map(|child| match child {
+                        Item::Element(element) => Some(element),
+                        _ => None,
+                    }),
+            );
+        }
+
+        while let Some(element) = stack.pop() {
+            f(element);
+
+            // push in reverse so the first child is visited next
+            stack.extend(
+                element
+                    .children
+                    .iter_mut()
+                    .rev()
+                    .filter_map(|child| match child {
+                        Item::Element(element) => Some(element),
+                        _ => None,
+                    }),
+            );
+        }
+    }
+
+    /** Get the text content of all text items within the element.
+
+    ```xml
+    <element>Hello<child>World</child></element>
+    ```
+
+    The above would result in "HelloWorld".
+
+    Parsing errors are silently ignored, see [`Element::get_text_content_with_warnings`] to detect them.*/
+    pub fn get_text_content(&self) -> String {
+        self.get_text_content_with_warnings().0
+    }
+
+    /** Get the text content like [`Element::get_text_content`], along with a warning for every text item that was skipped.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse_bytes(b"<a>ok<b>\xFF</b></a>")?;
+    let Item::Element(element) = &items[0] else {
+        panic!();
+    };
+
+    let (text, warnings) = element.get_text_content_with_warnings();
+
+    assert_eq!(text, "ok");
+    assert!(matches!(warnings[..], [Warning::Text(_)]));
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_text_content_with_warnings(&self) -> (String, Vec<Warning>) {
+        let mut text = String::new();
+        let mut warnings = Vec::new();
+        self.collect_text_content(&mut text, &mut warnings);
+        (text, warnings)
+    }
+
+    fn collect_text_content(&self, text: &mut String, warnings: &mut Vec<Warning>) {
+        for child in &self.children {
+            match child {
+                Item::Text(item) => match item.get_value() {
+                    Ok(value) => text.push_str(&value),
+                    Err(err) => warnings.push(Warning::Text(err.into())),
+                },
+                Item::Element(element) => element.collect_text_content(text, warnings),
+                _ => (),
+            }
+        }
+    }
+
+    /** Get the text content formatted as readable plain text, e.g. for previews.
+
+    Runs of whitespace are collapsed into a single space, and a line break is put wherever
+    an element named in `block_tags` starts or ends. Tags are matched like [`Element::matches_name`],
+    so wildcards like `*:p` work too. There are never empty lines or whitespace at the start and end.
+    Text is unescaped and CDATA sections are included, like in [`Element::get_character_data`],
+    so references to whitespace like `&#32;` are collapsed as well.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<body><h1>Title</h1>\n  <p>Some <b>bold</b>\n  text.</p><p>Line<br/>break</p></body>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(
+        element.get_readable_text(&["h1", "p", "br"]),
+        "Title\nSome bold text.\nLine\nbreak"
+    );
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored.*/
+    pub fn get_readable_text(&self, block_tags: &[&str]) -> String {
+        let is_block = |element: &Element| block_tags.iter().any(|tag| element.matches_name(tag));
+
+        let mut text = String::new();
+        let mut line_break = false;
+        let mut space = false;
+
+        for event in self.walk() {
+            let value = match event {
+                WalkEvent::Enter(element) | WalkEvent::Exit(element) => {
+                    line_break |= is_block(element);
+                    continue;
+                }
+                WalkEvent::Leaf(Item::Text(Other::Text(text))) => text.unescape().ok(),
+                WalkEvent::Leaf(Item::CData(Other::CData(cdata))) => {
+                    std::str::from_utf8(cdata).ok().map(Cow::Borrowed)
+                }
+                WalkEvent::Leaf(_) => None,
+            };
+            let Some(value) = value else {
+                continue;
+            };
+
+            for char in value.chars() {
+                if char.is_ascii() && is_xml_whitespace(char as u8) {
+                    space = true;
+                    continue;
+                }
+                if !text.is_empty() {
+                    if line_break {
+                        text.push('\n');
+                    } else if space {
+                        text.push(' ');
+                    }
+                }
+                line_break = false;
+                space = false;
+                text.push(char);
+            }
+        }
+
+        text
+    }
+
+    /** Get the text content of the text items directly within the element.
+
+    Unlike [`Element::get_text_content`], text within child elements is not included.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<label>Name: <input>default</input> (required)</label>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_own_text(), "Name:  (required)");
+    assert_eq!(element.get_text_content(), "Name: default (required)");
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored.*/
+    pub fn get_own_text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                Item::Text(text) => text.get_value().ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /** Get the character data of all text and CDATA items within the element.
+
+    Unlike [`Element::get_text_content`], which only includes text items as they are stored (escaped),
+    this unescapes text and also includes the content of CDATA sections, in document order.
+    The result is the text a reader of the document would see.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<script>a &lt; b<![CDATA[ && c < d]]></script>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_text_content(), "a &lt; b");
+    assert_eq!(element.get_character_data(), "a < b && c < d");
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored.*/
+    pub fn get_character_data(&self) -> String {
+        let mut data = String::new();
+        self.collect_character_data(&mut data);
+        data
+    }
+
+    fn collect_character_data(&self, data: &mut String) {
+        for child in &self.children {
+            match child {
+                Item::Text(Other::Text(text)) => {
+                    if let Ok(text) = text.unescape() {
+                        data.push_str(&text);
+                    }
+                }
+                Item::CData(Other::CData(cdata)) => {
+                    if let Ok(cdata) = std::str::from_utf8(cdata) {
+                        data.push_str(cdata);
+                    }
+                }
+                Item::Element(element) => element.collect_character_data(data),
+                _ => (),
+            }
+        }
+    }
+
+    /** Check if any text item within the element contains the needle.
+
+    Every text item is searched on its own, after unescaping, so matches never span several items.
+    If `ignore_case` is set, the text and the needle are compared in lowercase.
+
+    Parsing errors are silently ignored.*/
+    pub fn text_matches(&self, needle: &str, ignore_case: bool) -> bool {
+        self.children.iter().any(|child| match child {
+            Item::Text(text) => text_contains(text, needle, ignore_case),
+            Item::Element(element) => element.text_matches(needle, ignore_case),
+            _ => false,
+        })
+    }
+
+    /** Get all text items within the element that contain the needle, in document order.
+
+    Matches like [`Element::text_matches`].
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<doc><p>Rust &amp; XML</p><p>Nothing here</p><p>rust</p></doc>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    let found = element.find_text_nodes_containing("rust", true);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].get_value().unwrap(), "Rust &amp; XML");
+
+    assert!(element.text_matches("& XML", false));
+    assert!(!element.text_matches("RUST", false));
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn find_text_nodes_containing(&self, needle: &str, ignore_case: bool) -> Vec<&Other<'a>> {
+        let mut found = Vec::new();
+        self.collect_text_nodes_containing(needle, ignore_case, &mut found);
+        found
+    }
+
+    fn collect_text_nodes_containing<'s>(
+        &'s self,
+        needle: &str,
+        ignore_case: bool,
+        found: &mut Vec<&'s Other<'a>>,
+    ) {
+        for child in &self.children {
+            match child {
+                Item::Text(text) if text_contains(text, needle, ignore_case) => found.push(text),
+                Item::Element(element) => {
+                    element.collect_text_nodes_containing(needle, ignore_case, found)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /** Get all attributes as raw keys and values, without decoding them.
+
+    Values are returned as stored (escaped), borrowed from the element.
+    No `String` is allocated and no UTF-8 validation takes place.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse(r#"<a x="1" y="&amp;"/>"#)?[0] else {
+        panic!();
+    };
+
+    let entries: Vec<_> = element.attribute_entries().collect();
+
+    assert_eq!(entries[0].0, b"x");
+    assert_eq!(&*entries[1].1, b"&amp;");
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored.*/
+    pub fn attribute_entries(&self) -> impl Iterator<Item = (&[u8], Cow<'_, [u8]>)> {
+        self.element
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .map(|attr| (attr.key.into_inner(), attr.value))
+    }
+
+    /** Get all attributes in the order they appear in the source.
+
+    Use this instead of [`Element::get_attributes`] when the order matters.
+    Values are returned as stored, without unescaping.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse(r#"<a z="1" b="2" m="3"/>"#)?[0] else {
+        panic!();
+    };
+
+    let keys: Vec<String> = element.get_all_attributes().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["z", "b", "m"]);
+    # Ok::<(), Error>(())
+    ```
+
+    Parsing errors are silently ignored, see [`Element::get_all_attributes_with_warnings`] to detect them.*/
+    pub fn get_all_attributes(&'a self) -> impl Iterator<Item = (String, String)> + 'a {
+        self.attribute_entries()
+            .map(|(key, value)| (u8_to_string(key), String::from_utf8(value.into_owned())))
+            .filter_map(|attr| {
+                if attr.0.is_err() || attr.1.is_err() {
+                    return None;
+                }
+                Some((attr.0.unwrap(), attr.1.unwrap()))
+            })
+    }
+
+    /** Iterate over all attributes lazily, in source order, reporting errors instead of skipping them.
+
+    Unlike [`Element::get_all_attributes`], a malformed attribute or one that is not valid UTF-8 is yielded as an error,
+    and iteration continues after it. Values are returned as stored, without unescaping.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse(r#"<a x="1" y="2" x="3"/>"#)?[0] else {
+        panic!();
+    };
+
+    let mut attributes = element.attributes();
+
+    assert_eq!(attributes.next().unwrap()?, (String::from("x"), String::from("1")));
+    assert_eq!(attributes.next().unwrap()?, (String::from("y"), String::from("2")));
+    assert!(attributes.next().unwrap().is_err());
+    assert!(attributes.next().is_none());
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn attributes(&self) -> impl Iterator<Item = Result<(String, String), Error>> + '_ {
+        self.element.attributes().map(|attr| {
+            let attr = attr?;
+            Ok((qname_to_string(&attr.key)?, u8_to_string(&attr.value)?))
+        })
+    }
+
+    /** Get all attributes like [`Element::get_all_attributes`], along with a warning for every attribute that was skipped.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse_bytes(b"<a x=\"1\" y=\"\xFF\" x=\"2\"/>")?;
+    let Item::Element(element) = &items[0] else {
+        panic!();
+    };
+
+    let (attributes, warnings) = element.get_all_attributes_with_warnings();
+
+    assert_eq!(attributes, [(String::from("x"), String::from("1"))]);
+    assert_eq!(warnings.len(), 2);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_all_attributes_with_warnings(&self) -> (Vec<(String, String)>, Vec<Warning>) {
+        let mut attributes = Vec::new();
+        let mut warnings = Vec::new();
+        for attr in self.attributes() {
+            match attr {
+                Ok(attr) => attributes.push(attr),
+                Err(err) => warnings.push(Warning::Attribute(err)),
+            }
+        }
+        (attributes, warnings)
+    }
+
+    /** Get a map of all attributes.
+
+    The map doesn't keep the order of the attributes, see [`Element::get_all_attributes`] for that.
+    If an attribute occurs multiple times, the last occurence is used.
+
+    Parsing errors are silently ignored.*/
+    pub fn get_attributes(&self) -> HashMap<String, String> {
+        HashMap::from_iter(self.get_all_attributes())
+    }
+
+    /** Get the number of attributes without decoding them.
+
+    Malformed attributes are not counted, matching [`Element::get_all_attributes`].
+    Since duplicate attributes are malformed, only their first occurrence is counted.*/
+    pub fn attribute_count(&self) -> usize {
+        self.attribute_entries().count()
+    }
+
+    /** Get an attribute.
+
+    Attribute values are stored as written in the source, so references like `&#xA;` are returned as they are
+    and written back byte for byte. Use [`Element::get_attribute_cow`] or [`Element::get_attribute_normalized`]
+    for the decoded value.*/
+    pub fn get_attribute(&self, key: &str) -> Result<Option<String>, Error> {
+        let Some(attr) = self.element.try_get_attribute(key)? else {
+            return Ok(None);
+        };
+        match u8_to_string(&attr.value) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => Err(Error::NonDecodable(Some(err.utf8_error()))),
+        }
+    }
+
+    /** Get every value of an attribute, in source order.
+
+    Duplicate attributes are malformed, but they are kept by parsing, e.g. in [`parse_lenient`](crate::parse_lenient).
+    While the other getters only see one of the values, this returns all of them.
+    Like [`Element::get_attribute`], values are returned as stored.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse(r#"<a x="1" y="2" x="3"/>"#)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_attribute_all("x")?, ["1", "3"]);
+    assert!(element.get_attribute_all("z")?.is_empty());
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_attribute_all(&self, key: &str) -> Result<Vec<String>, Error> {
+        let mut values = Vec::new();
+        for attr in self.element.attributes().with_checks(false) {
+            let attr = attr?;
+            if attr.key.as_ref() == key.as_bytes() {
+                values.push(u8_to_string(&attr.value)?);
+            }
+        }
+        Ok(values)
+    }
+
+    /** Get an attribute, comparing keys ASCII-case-insensitively.
+
+    If several keys differ only by case, the value of the first one is returned.
+    Like [`Element::get_attribute`], the value is returned as stored.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse(r#"<div Class="a" class="b"/>"#)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_attribute_ci("CLASS")?.unwrap(), "a");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_attribute_ci(&self, key: &str) -> Result<Option<String>, Error> {
+        for attr in self.element.attributes() {
+            let attr = attr?;
+            if attr.key.as_ref().eq_ignore_ascii_case(key.as_bytes()) {
+                return Ok(Some(u8_to_string(&attr.value)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /** Get an attribute by its local name, ignoring any namespace prefix.
+
+    `"href"` matches `href`, `xlink:href` and `x:href`. Namespace declarations are not matched by their prefix,
+    so `"x"` doesn't match `xmlns:x`. If several attributes share the local name, the value of the first one is returned.
+    Like [`Element::get_attribute`], the value is returned as stored.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r##"<use xmlns:l="http://www.w3.org/1999/xlink" l:href="#icon"/>"##;
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_attribute_local("href")?.unwrap(), "#icon");
+    assert_eq!(element.get_attribute_local("l")?, None);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_attribute_local(&self, local: &str) -> Result<Option<String>, Error> {
+        for attr in self.element.attributes() {
+            let attr = attr?;
+            if attr.key.as_namespace_binding().is_some() {
+                continue;
+            }
+            if attr.key.local_name().as_ref() == local.as_bytes() {
+                return Ok(Some(u8_to_string(&attr.value)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /** Get an attribute, unescaped, without allocating if possible.
+
+    Unlike [`Element::get_attribute`], entities like `&amp;` are unescaped.
+    If the value doesn't contain any, it is borrowed from the element instead of copied.
+
+    ```rust
+    # use ilex_xml::*;
+    # use std::borrow::Cow;
+    let xml = r#"<a plain="x" escaped="x &amp; y"/>"#;
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert!(matches!(element.get_attribute_cow("plain")?, Some(Cow::Borrowed("x"))));
+    assert_eq!(element.get_attribute_cow("escaped")?.unwrap(), "x & y");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_attribute_cow(&self, key: &str) -> Result<Option<Cow<'_, str>>, Error> {
+        let Some(attr) = self.element.try_get_attribute(key)? else {
+            return Ok(None);
+        };
+        Ok(Some(attr.unescape_value()?))
+    }
+
+    /** Get an attribute, normalized like a conformant XML parser would.
+
+    Applies attribute-value normalization for attributes of type CDATA:
+    line breaks (`\r\n`, `\r`, `\n`) and tabs written literally are replaced with a single space each,
+    then references like `&amp;` or `&#xA;` are unescaped. Characters written as references are kept as they are.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<a title=\"two\r\n\tlines&#xA;\"/>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_attribute_normalized("title")?.unwrap(), "two  lines\n");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_attribute_normalized(&self, key: &str) -> Result<Option<String>, Error> {
+        let Some(raw) = self.get_attribute(key)? else {
+            return Ok(None);
+        };
+        let normalized = raw.replace("\r\n", " ").replace(['\t', '\r', '\n'], " ");
+        Ok(Some(unescape(&normalized)?.into_owned()))
+    }
+
+    /** Get an attribute and parse it.
+
+    Whitespace around the value is ignored.
+    Returns `Ok(None)` if the attribute is missing and [`AttributeValueError::Parse`] if it is present but cannot be parsed.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = r#"<rect width="100" height="wide"/>"#;
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.get_parsed_attribute::<u32>("width").unwrap(), Some(100));
+    assert_eq!(element.get_parsed_attribute::<u32>("depth").unwrap(), None);
+    assert!(matches!(
+        element.get_parsed_attribute::<u32>("height"),
+        Err(AttributeValueError::Parse(_))
+    ));
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn get_parsed_attribute<T: FromStr>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, AttributeValueError<T::Err>> {
+        let Some(value) = self.get_attribute(key).map_err(AttributeValueError::Xml)? else {
+            return Ok(None);
+        };
+        match value.trim().parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => Err(AttributeValueError::Parse(err)),
+        }
+    }
+
+    /** Get an attribute as a floating point number. See [`Element::get_parsed_attribute`]. */
+    pub fn get_f64_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Option<f64>, AttributeValueError<ParseFloatError>> {
+        self.get_parsed_attribute(key)
+    }
+
+    /** Get an attribute as a signed integer. See [`Element::get_parsed_attribute`]. */
+    pub fn get_i64_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Option<i64>, AttributeValueError<ParseIntError>> {
+        self.get_parsed_attribute(key)
+    }
+
+    /** Get an attribute as an unsigned integer. See [`Element::get_parsed_attribute`]. */
+    pub fn get_u64_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Option<u64>, AttributeValueError<ParseIntError>> {
+        self.get_parsed_attribute(key)
+    }
+
+    /** Get the length in bytes of an attribute value once serialized, excluding the quotes.
+
+    Attribute values are stored in their escaped form, so entities like `&amp;` are accounted for.*/
+    pub fn get_attribute_serialized_len(&self, key: &str) -> Result<Option<usize>, Error> {
+        Ok(self
+            .element
+            .try_get_attribute(key)?
+            .map(|attr| attr.value.len()))
+    }
+
+    /** Check if the element has the attribute. */
+    pub fn has_attribute(&self, key: &str) -> bool {
+        let Ok(result) = self.element.try_get_attribute(key) else {
+            return false;
+        };
+        result.is_some()
     }
 
     /** Replace all attributes with new ones. */
@@ -266,51 +1656,556 @@ impl<'a> Element<'a> {
         self.element.extend_attributes(attrs);
     }
 
-    /** Add or replace an attribute. */
+    /** Add or replace an attribute.
+
+    The value is escaped. A replaced attribute keeps its position and all other attributes are left untouched,
+    so editing a parsed document doesn't reorder its attributes. New attributes are appended.
+    Other attributes are written as before, even if they are malformed, but separated by a single space.
+    If the key occurs multiple times, only the first occurrence is replaced.
+
+    ```rust
+    # use ilex_xml::*;
+    let (mut items, _) = parse_lenient("<a id=b  k='v' k=\"2\"/>");
+    let Item::Element(element) = &mut items[0] else {
+        panic!();
+    };
+
+    element.set_attribute("k", "w");
+
+    assert_eq!(element.to_string(), "<a id=b k=\"w\" k=\"2\"/>");
+    # Ok::<(), Error>(())
+    ```*/
     pub fn set_attribute(&mut self, key: &str, value: &str) {
-        let mut attributes = self.get_attributes();
-        attributes.insert(String::from(key), String::from(value));
-        self.set_attributes(attributes);
+        let value = escape(value);
+        let written = format!(r#"{key}="{value}""#);
+
+        let name_len = self.element.name().as_ref().len();
+        let mut content = self.element[..name_len].to_vec();
+        let mut found = false;
+        for span in attribute_spans(&self.element, name_len) {
+            content.push(b' ');
+            if !found && &self.element[span.key.clone()] == key.as_bytes() {
+                found = true;
+                content.extend_from_slice(written.as_bytes());
+            } else {
+                content.extend_from_slice(&self.element[span.range()]);
+            }
+        }
+        if !found {
+            content.push(b' ');
+            content.extend_from_slice(written.as_bytes());
+        }
+
+        if self.set_tag_content(content, name_len) {
+            return;
+        }
+
+        // not valid UTF-8, so rebuild the tag from its well-formed attributes
+        let mut found = false;
+        let mut attributes: Vec<(Vec<u8>, Vec<u8>)> = self
+            .element
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .map(|attr| {
+                if attr.key.as_ref() == key.as_bytes() && !found {
+                    found = true;
+                    (attr.key.as_ref().to_vec(), value.as_bytes().to_vec())
+                } else {
+                    (attr.key.as_ref().to_vec(), attr.value.to_vec())
+                }
+            })
+            .collect();
+
+        if !found {
+            attributes.push((key.as_bytes().to_vec(), value.as_bytes().to_vec()));
+        }
+
+        self.set_raw_attributes(&attributes);
     }
 
-    /** Change the tag name. */
-    pub fn set_name(&mut self, name: &'a str) {
-        self.element.set_name(name.as_bytes());
+    /** Add or replace an attribute, leaving every other byte of the tag exactly as it is.
+
+    Unlike [`Element::set_attribute`], the attributes are not rewritten: only the value of the replaced attribute
+    is changed, so whitespace, quote style, and even malformed attributes elsewhere in the tag are kept.
+    The value is escaped, including both kinds of quotes, so it is safe within either.
+    An unquoted value, or a key without a value, gets a value in double quotes.
+    If the key occurs multiple times, only the first occurrence is changed.
+    A new attribute is added after the last one, in double quotes.
+
+    Fails with `Error::NonDecodable` if the tag is not valid UTF-8. The element is unchanged then.
+
+    ```rust
+    # use ilex_xml::*;
+    let mut items = parse("<a  x = 'old'\n   y=\"2\" />")?;
+    let Item::Element(element) = &mut items[0] else {
+        panic!();
+    };
+
+    element.set_attribute_preserving("x", "new")?;
+    element.set_attribute_preserving("z", "3")?;
+
+    assert_eq!(element.to_string(), "<a  x = 'new'\n   y=\"2\" z=\"3\" />");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn set_attribute_preserving(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let content =
+            std::str::from_utf8(&self.element).map_err(|err| Error::NonDecodable(Some(err)))?;
+        let name_len = self.element.name().as_ref().len();
+        let value = escape(value);
+
+        let existing = attribute_spans(content.as_bytes(), name_len)
+            .into_iter()
+            .find(|span| &content.as_bytes()[span.key.clone()] == key.as_bytes());
+
+        let spliced = match existing {
+            Some(AttributeSpan {
+                value: Some(range),
+                quote: Some(_),
+                ..
+            }) => format!(
+                "{}{value}{}",
+                &content[..range.start],
+                &content[range.end..]
+            ),
+            Some(AttributeSpan {
+                value: Some(range),
+                quote: None,
+                ..
+            }) => format!(
+                r#"{}"{value}"{}"#,
+                &content[..range.start],
+                &content[range.end..]
+            ),
+            Some(AttributeSpan {
+                key: range,
+                value: None,
+                ..
+            }) => format!(
+                r#"{}="{value}"{}"#,
+                &content[..range.end],
+                &content[range.end..]
+            ),
+            None => {
+                let end = content.trim_end_matches([' ', '\t', '\r', '\n']).len();
+                format!(r#"{} {key}="{value}"{}"#, &content[..end], &content[end..])
+            }
+        };
+
+        self.element = BytesStart::from_content(spliced, name_len);
+        Ok(())
+    }
+
+    /** Add or replace an attribute with any value implementing `Display`.
+
+    The formatted value is escaped, like in [`Element::set_attribute`].
+
+    ```rust
+    # use ilex_xml::*;
+    let mut element = Element::new("rect", true);
+
+    element.set_attribute_display("width", 100);
+    element.set_attribute_display("opacity", 0.5);
+
+    assert_eq!(element.to_string(), r#"<rect width="100" opacity="0.5"/>"#);
+    assert_eq!(element.get_i64_attribute("width").unwrap(), Some(100));
+    ```*/
+    pub fn set_attribute_display(&mut self, key: &str, value: impl Display) {
+        self.set_attribute(key, &value.to_string());
     }
 
-    /** Get the tag name. */
-    pub fn get_name(&self) -> Result<String, FromUtf8Error> {
-        qname_to_string(&self.element.name())
+    /** Remove an attribute.
+
+    Returns whether the attribute was present. If it isn't, the element is left unchanged.
+    The remaining attributes keep their order and are written as before, even if they are malformed.*/
+    pub fn remove_attribute(&mut self, key: &str) -> bool {
+        self.retain_raw_attributes(|attr_key| attr_key != key.as_bytes()) > 0
     }
-}
 
-impl ToStringSafe for Element<'_> {
-    fn to_string_safe(&self) -> Result<String, Error> {
-        let mut writer = Writer::new(Cursor::new(Vec::new()));
+    /** Remove an attribute and return its value, like `HashMap::remove`.
 
-        for event in self.get_all_events() {
-            let result = writer.write_event(event);
-            if result.is_err() {
-                return Err(result.unwrap_err());
+    The value is returned as stored, like in [`Element::get_attribute`].
+    If the key occurs multiple times, the first value is returned and all occurrences are removed.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(mut element) = parse(r#"<a old="x" keep="1"/>"#)?.remove(0) else {
+        panic!();
+    };
+
+    let value = element.take_attribute("old")?.unwrap();
+    element.set_attribute("new", &value);
+
+    assert_eq!(element.to_string(), r#"<a keep="1" new="x"/>"#);
+    assert_eq!(element.take_attribute("old")?, None);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn take_attribute(&mut self, key: &str) -> Result<Option<String>, Error> {
+        let value = self.get_attribute(key)?;
+        if value.is_some() {
+            self.remove_attribute(key);
+        }
+        Ok(value)
+    }
+
+    /** Remove all attributes. */
+    pub fn clear_attributes(&mut self) {
+        self.element.clear_attributes();
+    }
+
+    /** Get a boolean flag attribute.
+
+    An attribute is considered true if it is present and its value is `true`, `1`, `yes` or empty.
+    Values are compared case-sensitively.
+    Any other value, as well as the absence of the attribute, is considered false.*/
+    pub fn get_bool_attribute(&self, key: &str) -> Result<bool, Error> {
+        let value = self.get_attribute(key)?;
+        Ok(matches!(value.as_deref(), Some("true" | "1" | "yes" | "")))
+    }
+
+    /** Set a boolean flag attribute.
+
+    If `value` is true, the attribute is set to `true`. Otherwise it is removed.*/
+    pub fn set_bool_attribute(&mut self, key: &str, value: bool) {
+        if value {
+            self.set_attribute(key, "true");
+        } else {
+            self.remove_attribute(key);
+        }
+    }
+
+    /** Keep only the attributes whose raw key matches the predicate. Returns the number of removed attributes.
+
+    The tag is only rewritten if an attribute is removed. The remaining attributes are kept as they are written,
+    even if they are malformed, but separated by a single space.
+    Tags which are not valid UTF-8 are rebuilt from their well-formed attributes instead.*/
+    fn retain_raw_attributes(&mut self, mut keep: impl FnMut(&[u8]) -> bool) -> usize {
+        let name_len = self.element.name().as_ref().len();
+        let mut content = self.element[..name_len].to_vec();
+        let mut removed = 0;
+        for span in attribute_spans(&self.element, name_len) {
+            if keep(&self.element[span.key.clone()]) {
+                content.push(b' ');
+                content.extend_from_slice(&self.element[span.range()]);
+            } else {
+                removed += 1;
             }
         }
 
-        match String::from_utf8(writer.into_inner().into_inner()) {
-            Ok(str) => Ok(str),
-            Err(err) => Err(Error::NonDecodable(Some(err.utf8_error()))),
+        if removed == 0 || self.set_tag_content(content, name_len) {
+            return removed;
+        }
+
+        let kept: Vec<(Vec<u8>, Vec<u8>)> = self
+            .element
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .filter(|attr| keep(attr.key.as_ref()))
+            .map(|attr| (attr.key.as_ref().to_vec(), attr.value.to_vec()))
+            .collect();
+        self.set_raw_attributes(&kept);
+
+        removed
+    }
+
+    /** Replace the raw content of the tag, which is the name followed by the attributes as they are written.
+
+    Returns `false` without changing anything if the content is not valid UTF-8.*/
+    fn set_tag_content(&mut self, content: Vec<u8>, name_len: usize) -> bool {
+        let Ok(content) = String::from_utf8(content) else {
+            return false;
+        };
+        self.element = BytesStart::from_content(content, name_len);
+        true
+    }
+
+    /** Replace all attributes with already escaped keys and values.
+
+    Attributes are written in double quotes, so a `"` in a value taken from a single-quoted attribute is escaped.*/
+    pub(crate) fn set_raw_attributes(&mut self, attributes: &[(Vec<u8>, Vec<u8>)]) {
+        self.element.clear_attributes();
+        self.element
+            .extend_attributes(attributes.iter().map(|(key, value)| Attribute {
+                key: QName(key),
+                value: escape_quotes(value),
+            }));
+    }
+
+    /** Check if two elements are equal, ignoring how they are written.
+
+    Unlike `==`, this ignores the `self_closing` flag, so ```<x/>``` and ```<x></x>``` are equal.
+    Attributes are compared by key and value in order, so whitespace between attributes doesn't matter either.
+    Children are compared recursively in the same way.*/
+    pub fn eq_ignoring_style(&self, other: &Element) -> bool {
+        self.element.name() == other.element.name()
+            && self.element.attributes().eq(other.element.attributes())
+            && self.children.len() == other.children.len()
+            && self
+                .children
+                .iter()
+                .zip(other.children.iter())
+                .all(|pair| match pair {
+                    (Item::Element(a), Item::Element(b)) => a.eq_ignoring_style(b),
+                    (a, b) => a == b,
+                })
+    }
+
+    /** Check if two elements serialize to the same XML.
+
+    Compares the output of [`ToStringSafe::to_string_safe`], so unlike `==`, elements built differently
+    are equal as long as they are written the same way.
+    Being based on the serialized form, it is sensitive to everything visible in it, like attribute order,
+    quotes and whitespace between attributes, and ```<x/>``` differs from ```<x></x>```.
+    See [`Element::eq_ignoring_style`] for a comparison ignoring those.
+
+    Fails if either element can't be serialized.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(parsed) = parse(r#"<a x="1"/>"#)?.remove(0) else {
+        panic!();
+    };
+    let built = Element::with_attributes("a", [("x", "1")]);
+
+    assert!(parsed.renders_same(&built)?);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn renders_same(&self, other: &Element) -> Result<bool, Error> {
+        Ok(self.to_string_safe()? == other.to_string_safe()?)
+    }
+
+    /** Compute a fingerprint of the element and its descendants, without serializing them.
+
+    Elements that are equal according to [`Element::eq_ignoring_style`] have the same hash.
+    Additionally, the order of attributes doesn't affect the hash, so it can be used as a cache key for subtrees
+    that only differ in how they are written. Like any hash, different elements may collide.
+
+    The hash is stable within a build of the program, but may change with the Rust version, so it shouldn't be persisted.
+
+    ```rust
+    # use ilex_xml::*;
+    let items = parse(r#"<a x="1" y="2"><b/></a><a y="2" x="1"><b></b></a><a x="1"/>"#)?;
+
+    let hashes: Vec<u64> = items
+        .iter()
+        .map(|item| match item {
+            Item::Element(element) => element.content_hash(),
+            _ => panic!(),
+        })
+        .collect();
+
+    assert_eq!(hashes[0], hashes[1]);
+    assert_ne!(hashes[0], hashes[2]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash_content(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_content(&self, hasher: &mut DefaultHasher) {
+        self.element.name().as_ref().hash(hasher);
+
+        // combined by addition, so the order doesn't matter
+        let attributes = self
+            .element
+            .attributes()
+            .filter_map(|attr| attr.ok())
+            .map(|attr| {
+                let mut hasher = DefaultHasher::new();
+                attr.key.as_ref().hash(&mut hasher);
+                attr.value.hash(&mut hasher);
+                hasher.finish()
+            })
+            .fold(0, u64::wrapping_add);
+        attributes.hash(hasher);
+
+        self.children.len().hash(hasher);
+        for child in &self.children {
+            child.kind().hash(hasher);
+            match child {
+                Item::Element(element) => element.hash_content(hasher),
+                Item::Comment(other)
+                | Item::Text(other)
+                | Item::DocType(other)
+                | Item::CData(other)
+                | Item::Decl(other)
+                | Item::PI(other) => other.sort_key().hash(hasher),
+            }
+        }
+    }
+
+    /** Stringify the element with indentation.
+
+    Every tag is put on its own line and indented by its depth relative to this element, using `indent` once per level.
+    Text is kept inline, so existing whitespace is preserved and mixed content is not reflowed.
+
+    ```rust
+    # use ilex_xml::*;
+    let xml = "<a><b>text</b><c/></a>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.to_string_pretty("  ")?, "<a>\n  <b>text</b>\n  <c/>\n</a>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn to_string_pretty(&self, indent: &str) -> Result<String, Error> {
+        self.to_string_with(&SerializeOptions::pretty(indent))
+    }
+
+    /** Stringify the element using the given options. */
+    pub fn to_string_with(&self, options: &SerializeOptions) -> Result<String, Error> {
+        events_to_string(self.get_all_events(), options)
+    }
+
+    /** Stringify the element including its own tags, like `outerHTML` in the DOM.
+
+    Equivalent to [`ToStringSafe::to_string_safe`].*/
+    pub fn outer_xml(&self) -> Result<String, Error> {
+        self.to_string_safe()
+    }
+
+    /** Stringify only the children of the element, without its own tags, like `innerHTML` in the DOM.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse("<p>Hello <b>world</b>!</p>")?[0] else {
+        panic!();
+    };
+
+    assert_eq!(element.outer_xml()?, "<p>Hello <b>world</b>!</p>");
+    assert_eq!(element.inner_xml()?, "Hello <b>world</b>!");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn inner_xml(&self) -> Result<String, Error> {
+        events_to_string(
+            self.children
+                .iter()
+                .flat_map(|child| child.get_all_events()),
+            &SerializeOptions::default(),
+        )
+    }
+
+    /** Replace the children with the items parsed from an XML fragment, like assigning `innerHTML` in the DOM.
+
+    The fragment is parsed like in [`parse`](crate::parse), so it may contain any number of top level items,
+    but must be well-formed. If it isn't, the error is returned and the children are left untouched.
+    The parsed items own their data, so the fragment doesn't need to outlive the element.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(mut element) = parse("<ul/>")?.remove(0) else {
+        panic!();
+    };
+
+    element.set_inner_xml("<li>a &amp; b</li><li>c</li>")?;
+    assert_eq!(element.to_string(), "<ul><li>a &amp; b</li><li>c</li></ul>");
+
+    assert!(element.set_inner_xml("<li>").is_err());
+    assert_eq!(element.children.len(), 2);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn set_inner_xml(&mut self, xml: &str) -> Result<(), Error> {
+        let children = parse_reader(xml.as_bytes())?;
+        self.set_children(children);
+        Ok(())
+    }
+
+    /** Change the tag name. */
+    pub fn set_name(&mut self, name: &str) {
+        self.element.set_name(name.as_bytes());
+    }
+
+    /** Check if the tag name equals the given name, without allocating. */
+    pub fn name_equals(&self, name: &str) -> bool {
+        self.element.name().as_ref() == name.as_bytes()
+    }
+
+    /** Check if the tag name matches a pattern with namespace prefix wildcards, without allocating.
+
+    - `*` matches any element.
+    - `*:local` matches `local` with any prefix, or without a prefix.
+    - `prefix:*` matches any element with the prefix `prefix`.
+    - Any other pattern must equal the name exactly, like with [`Element::name_equals`].
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(root) = &parse("<r><svg:rect/><rect/><svg:circle/></r>")?[0] else {
+        panic!();
+    };
+
+    let names = |pattern| -> Vec<String> {
+        root.find_descendants(&|item| matches!(item, Item::Element(el) if el.matches_name(pattern)))
+            .map(|item| item.to_string())
+            .collect()
+    };
+
+    assert_eq!(names("*:rect"), ["<svg:rect/>", "<rect/>"]);
+    assert_eq!(names("svg:*"), ["<svg:rect/>", "<svg:circle/>"]);
+    assert_eq!(names("rect"), ["<rect/>"]);
+    assert_eq!(names("*").len(), 3);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn matches_name(&self, pattern: &str) -> bool {
+        let name = self.element.name();
+        if pattern == "*" {
+            return true;
         }
+        if let Some(local) = pattern.strip_prefix("*:") {
+            return name.local_name().as_ref() == local.as_bytes();
+        }
+        if let Some(prefix) = pattern.strip_suffix(":*") {
+            return name
+                .prefix()
+                .is_some_and(|name_prefix| name_prefix.as_ref() == prefix.as_bytes());
+        }
+        name.as_ref() == pattern.as_bytes()
+    }
+
+    /** Get the tag name.
+
+    Fails with `Error::NonDecodable` if the name is not valid UTF-8,
+    so all fallible methods of `Element` share the crate's [`Error`] type.
+    A `FromUtf8Error` converts into it with `?` as well.*/
+    pub fn get_name(&self) -> Result<String, Error> {
+        Ok(qname_to_string(&self.element.name())?)
+    }
+}
+
+impl ToStringSafe for Element<'_> {
+    fn to_string_safe(&self) -> Result<String, Error> {
+        events_to_string(self.get_all_events(), &SerializeOptions::default())
     }
 }
 
 impl Display for Element<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = self.to_string_safe().unwrap();
-        write!(f, "{str}")
+        f.write_str(&events_to_string_lossy(self.get_all_events()))
+    }
+}
+
+impl Ord for Element<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (name, attributes) = self.element.split_at(self.element.name().as_ref().len());
+        let (other_name, other_attributes) =
+            other.element.split_at(other.element.name().as_ref().len());
+        name.cmp(other_name)
+            .then_with(|| attributes.cmp(other_attributes))
+            .then_with(|| self.children.cmp(&other.children))
+            .then(self.self_closing.cmp(&other.self_closing))
+    }
+}
+
+impl PartialOrd for Element<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl GetEvents for Element<'_> {
-    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event> + '_> {
+    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event<'_>> + '_> {
         if self.self_closing && self.children.is_empty() {
             Box::new(std::iter::once(Event::Empty(self.element.to_owned())))
         } else {
@@ -328,3 +2223,31 @@ impl GetEvents for Element<'_> {
         }
     }
 }
+
+/** Get the name of an item as used in a formatted path. */
+fn path_segment<'i>(item: &'i Item) -> Cow<'i, str> {
+    match item {
+        Item::Element(element) => String::from_utf8_lossy(element.element.name().into_inner()),
+        Item::Comment(_) => Cow::Borrowed("comment()"),
+        Item::Text(_) => Cow::Borrowed("text()"),
+        Item::DocType(_) => Cow::Borrowed("doctype()"),
+        Item::CData(_) => Cow::Borrowed("cdata()"),
+        Item::Decl(_) => Cow::Borrowed("declaration()"),
+        Item::PI(_) => Cow::Borrowed("processing-instruction()"),
+    }
+}
+
+/** Check if a text item contains the needle after unescaping. */
+fn text_contains(text: &Other, needle: &str, ignore_case: bool) -> bool {
+    let Other::Text(text) = text else {
+        return false;
+    };
+    let Ok(text) = text.unescape() else {
+        return false;
+    };
+    if ignore_case {
+        text.to_lowercase().contains(&needle.to_lowercase())
+    } else {
+        text.contains(needle)
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{BufRead, BufReader};
+
+use flate2::bufread::GzDecoder;
+
+use crate::{parse_reader, Error, Item};
+
+/** Parse gzip-compressed XML from a buffered reader, like an `.svgz` file.
+
+Only available with the `flate2` feature.
+
+The input is decompressed while it is parsed, so the decompressed XML is never held in memory as a whole.
+Invalid compressed data fails with `Error::Io`.
+
+```rust
+# use ilex_xml::*;
+# use std::io::Write;
+# let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+# encoder.write_all(b"<svg><rect/></svg>").unwrap();
+# let compressed = encoder.finish().unwrap();
+let items = parse_gz_reader(compressed.as_slice())?;
+
+assert_eq!(items_to_string(&items), "<svg><rect/></svg>");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_gz_reader(reader: impl BufRead) -> Result<Vec<Item<'static>>, Error> {
+    parse_reader(BufReader::new(GzDecoder::new(reader)))
+}
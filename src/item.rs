@@ -1,10 +1,28 @@
-use std::fmt::Display;
+use std::{borrow::Cow, cmp::Ordering, fmt::Display};
 
 use quick_xml::events::Event;
 
-use crate::{util::GetEvents, Element, Other, ToStringSafe};
+use crate::{
+    serialize::events_to_string, util::GetEvents, DeclError, Element, Other, PIError,
+    SerializeOptions, ToStringSafe,
+};
 
-/** Any XML item. */
+/** Any XML item.
+
+Items are ordered first by their kind, in the order of the variants below,
+so elements come before comments, comments before text and so on.
+Items of the same kind are ordered like [`Element`] or [`Other`].
+The order is arbitrary, but stable and consistent with `==`, so it can be used to canonicalize items.
+
+```rust
+# use ilex_xml::*;
+let mut items = parse("text<b/><!--c--><a y='1'/><a x='2'/>")?;
+
+items.sort();
+
+assert_eq!(items_to_string(&items), "<a x='2'/><a y='1'/><b/><!--c-->text");
+# Ok::<(), Error>(())
+```*/
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Item<'a> {
     /** Element ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```. */
@@ -25,38 +43,96 @@ pub enum Item<'a> {
 
 impl<'a> Item<'a> {
     /** Create a new Element. */
-    pub fn new_element(name: &'a str, self_closing: bool) -> Self {
+    pub fn new_element(name: impl Into<Cow<'a, str>>, self_closing: bool) -> Self {
         Item::Element(Element::new(name, self_closing))
     }
 
     /** Create a new comment item. */
-    pub fn new_comment(content: &'a str) -> Self {
+    pub fn new_comment(content: impl Into<Cow<'a, str>>) -> Self {
         Item::Comment(Other::new_comment(content))
     }
 
     /** Create a new text item. */
-    pub fn new_text(content: &'a str) -> Self {
+    pub fn new_text(content: impl Into<Cow<'a, str>>) -> Self {
         Item::Text(Other::new_text(content))
     }
 
     /** Create a new doctype item. */
-    pub fn new_doctype(content: &'a str) -> Self {
+    pub fn new_doctype(content: impl Into<Cow<'a, str>>) -> Self {
         Item::DocType(Other::new_doctype(content))
     }
 
     /** Create a new character data item. */
-    pub fn new_cdata(content: &'a str) -> Self {
+    pub fn new_cdata(content: impl Into<Cow<'a, str>>) -> Self {
         Item::CData(Other::new_cdata(content))
     }
 
-    /** Create a new declaration item. */
-    pub fn new_decl(version: &str, encoding: Option<&str>, standalone: Option<&str>) -> Self {
-        Item::Decl(Other::new_decl(version, encoding, standalone))
+    /** Create character data items holding any content, split wherever it contains `]]>`.
+
+    See [`Other::new_cdata_sections`].*/
+    pub fn new_cdata_sections(content: &'a str) -> Vec<Self> {
+        Other::new_cdata_sections(content)
+            .into_iter()
+            .map(Item::CData)
+            .collect()
+    }
+
+    /** Create a new declaration item.
+
+    See [`Other::new_decl`] for the validation.*/
+    pub fn new_decl(
+        version: &str,
+        encoding: Option<&str>,
+        standalone: Option<&str>,
+    ) -> Result<Self, DeclError> {
+        Other::new_decl(version, encoding, standalone).map(Item::Decl)
+    }
+
+    /** Create a new declaration item without validating its parts.
+
+    See [`Other::new_decl_unchecked`].*/
+    pub fn new_decl_unchecked(
+        version: &str,
+        encoding: Option<&str>,
+        standalone: Option<&str>,
+    ) -> Self {
+        Item::Decl(Other::new_decl_unchecked(version, encoding, standalone))
+    }
+
+    /** Create a new processing instruction item.
+
+    See [`Other::new_pi`] for the validation.*/
+    pub fn new_pi(content: impl Into<Cow<'a, str>>) -> Result<Self, PIError> {
+        Other::new_pi(content).map(Item::PI)
+    }
+
+    /** Create a new processing instruction item without validating it.
+
+    See [`Other::new_pi_unchecked`].*/
+    pub fn new_pi_unchecked(content: impl Into<Cow<'a, str>>) -> Self {
+        Item::PI(Other::new_pi_unchecked(content))
+    }
+
+    /** Check if the item is text or CDATA made up of whitespace only.
+
+    See [`Other::is_whitespace`].*/
+    pub fn is_whitespace(&self) -> bool {
+        match self {
+            Item::Text(other) | Item::CData(other) => other.is_whitespace(),
+            _ => false,
+        }
     }
 
-    /** Create a new processing instruction item. */
-    pub fn new_pi(content: &'a str) -> Self {
-        Item::PI(Other::new_pi(content))
+    /** Stringify the item with indentation.
+
+    See [`Element::to_string_pretty`].*/
+    pub fn to_string_pretty(&self, indent: &str) -> Result<String, crate::Error> {
+        self.to_string_with(&SerializeOptions::pretty(indent))
+    }
+
+    /** Stringify the item using the given options. */
+    pub fn to_string_with(&self, options: &SerializeOptions) -> Result<String, crate::Error> {
+        events_to_string(self.get_all_events(), options)
     }
 }
 
@@ -88,8 +164,54 @@ impl Display for Item<'_> {
     }
 }
 
+impl Item<'_> {
+    /** Get the position of the variant, used for ordering. */
+    pub(crate) fn kind(&self) -> u8 {
+        match self {
+            Item::Element(_) => 0,
+            Item::Comment(_) => 1,
+            Item::Text(_) => 2,
+            Item::DocType(_) => 3,
+            Item::CData(_) => 4,
+            Item::Decl(_) => 5,
+            Item::PI(_) => 6,
+        }
+    }
+}
+
+impl Ord for Item<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.kind()
+            .cmp(&other.kind())
+            .then_with(|| match (self, other) {
+                (Item::Element(a), Item::Element(b)) => a.cmp(b),
+                (
+                    Item::Comment(a)
+                    | Item::Text(a)
+                    | Item::DocType(a)
+                    | Item::CData(a)
+                    | Item::Decl(a)
+                    | Item::PI(a),
+                    Item::Comment(b)
+                    | Item::Text(b)
+                    | Item::DocType(b)
+                    | Item::CData(b)
+                    | Item::Decl(b)
+                    | Item::PI(b),
+                ) => a.cmp(b),
+                _ => unreachable!(),
+            })
+    }
+}
+
+impl PartialOrd for Item<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl GetEvents for Item<'_> {
-    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event> + '_> {
+    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event<'_>> + '_> {
         match self {
             Item::Element(element) => element.get_all_events(),
             Item::Comment(comment) => comment.get_all_events(),
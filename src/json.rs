@@ -0,0 +1,87 @@
+use serde_json::{Map, Value};
+
+use crate::{Element, Item, Other};
+
+impl Element<'_> {
+    /** Convert the element into a JSON value.
+
+    Only available with the `serde_json` feature.
+
+    The element is mapped to a JSON object:
+    - Every attribute is stored as a string under its name prefixed with `@`.
+    - Text and character data directly inside the element is concatenated and stored under `#text`, if there is any.
+      Text consisting only of whitespace, like the indentation between child elements, is skipped.
+    - Child elements are converted recursively and stored in an array under their tag name, in document order.
+
+    Comments, processing instructions and other items are left out.
+    Names, values and text are unescaped. Parsing errors are silently ignored.
+
+    ```rust
+    # use ilex_xml::*;
+    # use serde_json::json;
+    let xml = r#"<book id="1"><title>Rust &amp; XML</title><tag>a</tag><tag>b</tag></book>"#;
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    assert_eq!(
+        element.to_json_value(),
+        json!({
+            "@id": "1",
+            "title": [{ "#text": "Rust & XML" }],
+            "tag": [{ "#text": "a" }, { "#text": "b" }],
+        })
+    );
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn to_json_value(&self) -> Value {
+        let mut object = Map::new();
+
+        for attr in self.element.attributes().filter_map(|attr| attr.ok()) {
+            let (Ok(key), Ok(value)) = (
+                std::str::from_utf8(attr.key.as_ref()),
+                attr.unescape_value(),
+            ) else {
+                continue;
+            };
+            object.insert(format!("@{key}"), Value::String(value.into_owned()));
+        }
+
+        let mut text = String::new();
+
+        for child in &self.children {
+            match child {
+                Item::Text(text) if text.is_whitespace() => (),
+                Item::Text(Other::Text(content)) => {
+                    if let Ok(content) = content.unescape() {
+                        text.push_str(&content);
+                    }
+                }
+                Item::CData(Other::CData(content)) => {
+                    if let Ok(content) = std::str::from_utf8(content) {
+                        text.push_str(content);
+                    }
+                }
+                Item::Element(element) => {
+                    let Ok(name) = element.get_name() else {
+                        continue;
+                    };
+                    let entry = object
+                        .entry(name)
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(array) = entry {
+                        array.push(element.to_json_value());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !text.is_empty() {
+            object.insert(String::from("#text"), Value::String(text));
+        }
+
+        Value::Object(object)
+    }
+}
@@ -21,15 +21,39 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+mod cursor;
+mod document;
 mod element;
+#[cfg(feature = "flate2")]
+mod gzip;
 mod item;
+#[cfg(feature = "serde_json")]
+mod json;
 mod other;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod parsing;
+mod schema;
+mod selector;
+mod serialize;
+mod stream;
+mod template;
 mod util;
+mod warning;
 
+pub use cursor::*;
+pub use document::*;
 pub use element::*;
+#[cfg(feature = "flate2")]
+pub use gzip::*;
 pub use item::*;
 pub use other::*;
 pub use parsing::*;
 pub use quick_xml::Error;
-pub use util::ToStringSafe;
+pub use schema::*;
+pub use selector::*;
+pub use serialize::{LineEnding, SerializeOptions};
+pub use stream::*;
+pub use template::*;
+pub use util::{GetEvents, ToStringSafe};
+pub use warning::*;
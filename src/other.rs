@@ -1,16 +1,77 @@
-use std::{fmt::Display, io::Cursor, string::FromUtf8Error};
+use std::{borrow::Cow, cmp::Ordering, fmt::Display, string::FromUtf8Error};
 
-use quick_xml::{
-    events::{BytesCData, BytesDecl, BytesPI, BytesText, Event},
-    Error, Writer,
-};
+use quick_xml::events::{BytesCData, BytesDecl, BytesPI, BytesText, Event};
 
 use crate::{
-    util::{u8_to_string, GetEvents},
-    ToStringSafe,
+    serialize::{events_to_string, events_to_string_lossy},
+    util::{escaped_text, is_xml_whitespace, qname_to_string, u8_to_string, GetEvents},
+    SerializeOptions, ToStringSafe,
 };
 
-/** Any XML item that is not an element. */
+/** The data of a processing instruction. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PIData {
+    /** Pseudo-attributes ```key="value"``` in source order. */
+    Attributes(Vec<(String, String)>),
+    /** Data that is not made up of pseudo-attributes. */
+    Raw(String),
+}
+
+/** An invalid part of a declaration. See [`Other::new_decl`]. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeclError {
+    /** The version is not `1.` followed by digits. */
+    Version(String),
+    /** The encoding is not a valid encoding name. */
+    Encoding(String),
+    /** Standalone is neither `yes` nor `no`. */
+    Standalone(String),
+}
+
+impl Display for DeclError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeclError::Version(version) => write!(f, "invalid XML version: {version:?}"),
+            DeclError::Encoding(encoding) => write!(f, "invalid encoding name: {encoding:?}"),
+            DeclError::Standalone(standalone) => {
+                write!(f, "standalone must be \"yes\" or \"no\": {standalone:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeclError {}
+
+/** An invalid processing instruction. See [`Other::new_pi`]. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PIError {
+    /** The target is empty or not a valid XML name. */
+    Target(String),
+    /** The target is `xml` in any case, which is reserved. `<?xml ...?>` is read as a declaration. */
+    ReservedTarget(String),
+    /** The content contains `?>`, which would end the processing instruction early. */
+    Terminator,
+}
+
+impl Display for PIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PIError::Target(target) => {
+                write!(f, "invalid processing instruction target: {target:?}")
+            }
+            PIError::ReservedTarget(target) => {
+                write!(f, "reserved processing instruction target: {target:?}")
+            }
+            PIError::Terminator => f.write_str("processing instruction contains \"?>\""),
+        }
+    }
+}
+
+impl std::error::Error for PIError {}
+
+/** Any XML item that is not an element.
+
+Ordered first by kind, in the order of the variants below, then bytewise by content as it is stored.*/
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Other<'a> {
     /** Comment ```<!-- ... -->```. */
@@ -28,33 +89,141 @@ pub enum Other<'a> {
 }
 
 impl<'a> Other<'a> {
-    /** Create a new comment. */
-    pub fn new_comment(content: &'a str) -> Self {
-        Other::Comment(BytesText::new(content))
+    /** Create a new comment.
+    ```rust
+        # use ilex_xml::Other;
+        let comment = Other::new_comment(" generated ");
+        assert_eq!(comment.to_string(), "<!-- generated -->");
+    ```*/
+    pub fn new_comment(content: impl Into<Cow<'a, str>>) -> Self {
+        Other::Comment(escaped_text(content))
     }
 
-    /** Create a new text. */
-    pub fn new_text(content: &'a str) -> Self {
-        Other::Text(BytesText::new(content))
+    /** Create a new text. The content is escaped.
+
+    Like all constructors, this accepts borrowed as well as owned strings.
+    ```rust
+        # use ilex_xml::Other;
+        let text: Other<'static> = Other::new_text(format!("{} & {}", 1, 2));
+        assert_eq!(text.to_string(), "1 &amp; 2");
+    ```*/
+    pub fn new_text(content: impl Into<Cow<'a, str>>) -> Self {
+        Other::Text(escaped_text(content))
     }
 
     /** Create a new doctype. */
-    pub fn new_doctype(content: &'a str) -> Self {
-        Other::DocType(BytesText::new(content))
+    pub fn new_doctype(content: impl Into<Cow<'a, str>>) -> Self {
+        Other::DocType(escaped_text(content))
     }
 
-    /** Create a new character data. */
-    pub fn new_cdata(content: &'a str) -> Self {
+    /** Create a new character data.
+
+    The content must not contain `]]>`, since that would end the section early and produce invalid XML.
+    Use [`Other::new_cdata_sections`] for content that may contain it.*/
+    pub fn new_cdata(content: impl Into<Cow<'a, str>>) -> Self {
         Other::CData(BytesCData::new(content))
     }
 
-    /** Create a new processing instruction. */
-    pub fn new_pi(content: &'a str) -> Self {
+    /** Create character data sections holding any content.
+
+    Wherever the content contains `]]>`, it is split between `]]` and `>` into a new section,
+    so the written XML is valid and reads back as the original content.
+    ```rust
+        # use ilex_xml::{Other, ToStringSafe};
+        let sections = Other::new_cdata_sections("a]]>b");
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].to_string(), "<![CDATA[a]]]]>");
+        assert_eq!(sections[1].to_string(), "<![CDATA[>b]]>");
+    ```*/
+    pub fn new_cdata_sections(content: &'a str) -> Vec<Self> {
+        let mut sections = Vec::new();
+        let mut rest = content;
+        while let Some(index) = rest.find("]]>") {
+            sections.push(Other::new_cdata(&rest[..index + 2]));
+            rest = &rest[index + 2..];
+        }
+        sections.push(Other::new_cdata(rest));
+        sections
+    }
+
+    /** Create a new processing instruction from its target, optionally followed by whitespace and data.
+
+    Fails if the target is not a valid XML name, if it is `xml` in any case, which is reserved
+    for the declaration, or if the content contains `?>`.
+    ```rust
+        # use ilex_xml::{Other, PIError};
+        let pi = Other::new_pi(r#"xml-stylesheet href="x.xsl""#)?;
+        assert_eq!(pi.to_string(), r#"<?xml-stylesheet href="x.xsl"?>"#);
+
+        assert!(matches!(Other::new_pi("xml foo"), Err(PIError::ReservedTarget(_))));
+        assert!(matches!(Other::new_pi(" php"), Err(PIError::Target(_))));
+        # Ok::<(), PIError>(())
+    ```*/
+    pub fn new_pi(content: impl Into<Cow<'a, str>>) -> Result<Self, PIError> {
+        let content = content.into();
+        if content.contains("?>") {
+            return Err(PIError::Terminator);
+        }
+        let target = content
+            .split(|c: char| c.is_ascii() && is_xml_whitespace(c as u8))
+            .next()
+            .unwrap_or_default();
+        if target.eq_ignore_ascii_case("xml") {
+            return Err(PIError::ReservedTarget(String::from(target)));
+        }
+        if !is_valid_name(target) {
+            return Err(PIError::Target(String::from(target)));
+        }
+        Ok(Self::new_pi_unchecked(content))
+    }
+
+    /** Create a new processing instruction without validating it.
+
+    The content is written as it is, so an invalid target produces a processing instruction
+    other parsers may reject or read as a declaration.*/
+    pub fn new_pi_unchecked(content: impl Into<Cow<'a, str>>) -> Self {
         Other::PI(BytesPI::new(content))
     }
 
-    /** Create a new declaration. */
-    pub fn new_decl(version: &str, encoding: Option<&str>, standalone: Option<&str>) -> Self {
+    /** Create a new declaration.
+
+    Fails if the version is not `1.` followed by digits, the encoding is not a valid encoding name
+    like `UTF-8`, or standalone is not `yes` or `no`.
+    ```rust
+        # use ilex_xml::{DeclError, Other};
+        let decl = Other::new_decl("1.0", Some("UTF-8"), Some("yes"))?;
+        assert_eq!(decl.to_string(), r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
+
+        assert!(matches!(Other::new_decl("banana", None, None), Err(DeclError::Version(_))));
+        # Ok::<(), DeclError>(())
+    ```*/
+    pub fn new_decl(
+        version: &str,
+        encoding: Option<&str>,
+        standalone: Option<&str>,
+    ) -> Result<Self, DeclError> {
+        if !is_valid_version(version) {
+            return Err(DeclError::Version(String::from(version)));
+        }
+        if let Some(encoding) = encoding.filter(|encoding| !is_valid_encoding(encoding)) {
+            return Err(DeclError::Encoding(String::from(encoding)));
+        }
+        if let Some(standalone) =
+            standalone.filter(|standalone| !matches!(*standalone, "yes" | "no"))
+        {
+            return Err(DeclError::Standalone(String::from(standalone)));
+        }
+        Ok(Self::new_decl_unchecked(version, encoding, standalone))
+    }
+
+    /** Create a new declaration without validating its parts.
+
+    The parts are written as they are, so invalid ones produce a declaration other parsers may reject.*/
+    pub fn new_decl_unchecked(
+        version: &str,
+        encoding: Option<&str>,
+        standalone: Option<&str>,
+    ) -> Self {
         Other::Decl(BytesDecl::new(version, encoding, standalone))
     }
 
@@ -77,7 +246,99 @@ impl<'a> Other<'a> {
         }
     }
 
-    fn get_event(&self) -> Event {
+    /** Check if the item is text or CDATA made up of XML whitespace only (space, tab, carriage return and line feed).
+
+    Character references in text are decoded, so `&#32;` counts as whitespace.
+    Text that can't be decoded is not considered whitespace.
+    ```rust
+        # use ilex_xml::Other;
+        assert!(Other::new_text(" \n\t").is_whitespace());
+        assert!(Other::new_cdata("  ").is_whitespace());
+        assert!(!Other::new_text(" a ").is_whitespace());
+        assert!(!Other::new_comment(" ").is_whitespace());
+    ```*/
+    pub fn is_whitespace(&self) -> bool {
+        match &self {
+            Other::Text(text) if text.contains(&b'&') => match text.unescape() {
+                Ok(text) => text.bytes().all(is_xml_whitespace),
+                Err(_) => false,
+            },
+            Other::Text(text) => text.iter().copied().all(is_xml_whitespace),
+            Other::CData(cdata) => cdata.iter().copied().all(is_xml_whitespace),
+            _ => false,
+        }
+    }
+
+    /** Get the length in bytes of the item once serialized, including its markup.
+
+    Text is stored in its escaped form, so entities like `&amp;` are accounted for.
+    The item is not serialized to compute the length.
+    ```rust
+        # use ilex_xml::Other;
+        let text = Other::new_text("a & b");
+        assert_eq!(text.serialized_len(), "a &amp; b".len());
+    ```*/
+    pub fn serialized_len(&self) -> usize {
+        match &self {
+            Other::Comment(event) => "<!--".len() + event.len() + "-->".len(),
+            Other::Text(event) => event.len(),
+            Other::DocType(event) => "<!DOCTYPE ".len() + event.len() + ">".len(),
+            Other::CData(event) => "<![CDATA[".len() + event.len() + "]]>".len(),
+            Other::Decl(event) => "<?".len() + event.len() + "?>".len(),
+            Other::PI(event) => "<?".len() + event.len() + "?>".len(),
+        }
+    }
+
+    /** Get the target of a processing instruction.
+
+    Returns `None` if the item is not a processing instruction.
+    ```rust
+        # use ilex_xml::Other;
+        let pi = Other::new_pi_unchecked(r#"xml-stylesheet type="text/xsl" href="x.xsl""#);
+        assert_eq!(pi.get_target()?.unwrap(), "xml-stylesheet");
+        # Ok::<(), std::string::FromUtf8Error>(())
+    ```*/
+    pub fn get_target(&self) -> Result<Option<String>, FromUtf8Error> {
+        let Other::PI(pi) = self else {
+            return Ok(None);
+        };
+        u8_to_string(pi.target()).map(Some)
+    }
+
+    /** Get the data of a processing instruction as pseudo-attributes.
+
+    If the data is made up of `key="value"` pairs, they are returned in order as [`PIData::Attributes`].
+    Otherwise the raw data is returned as [`PIData::Raw`].
+    Values are returned as written, without unescaping.
+
+    Returns `None` if the item is not a processing instruction.
+    ```rust
+        # use ilex_xml::{Other, PIData};
+        let pi = Other::new_pi_unchecked(r#"xml-stylesheet type="text/xsl" href="x.xsl""#);
+        let Some(PIData::Attributes(attrs)) = pi.get_pseudo_attributes()? else {
+            panic!();
+        };
+        assert_eq!(attrs[1], (String::from("href"), String::from("x.xsl")));
+        # Ok::<(), std::string::FromUtf8Error>(())
+    ```*/
+    pub fn get_pseudo_attributes(&self) -> Result<Option<PIData>, FromUtf8Error> {
+        let Other::PI(pi) = self else {
+            return Ok(None);
+        };
+
+        let mut attributes = Vec::new();
+        for attr in pi.attributes() {
+            let Ok(attr) = attr else {
+                let data = u8_to_string(pi.content())?;
+                return Ok(Some(PIData::Raw(String::from(data.trim_start()))));
+            };
+            attributes.push((qname_to_string(&attr.key)?, u8_to_string(&attr.value)?));
+        }
+
+        Ok(Some(PIData::Attributes(attributes)))
+    }
+
+    fn get_event(&self) -> Event<'_> {
         match &self {
             Other::Comment(event) => Event::Comment(event.to_owned()),
             Other::Text(event) => Event::Text(event.to_owned()),
@@ -89,33 +350,77 @@ impl<'a> Other<'a> {
     }
 }
 
-impl ToStringSafe for Other<'_> {
-    fn to_string_safe(&self) -> Result<String, crate::Error> {
-        let mut writer = Writer::new(Cursor::new(Vec::new()));
+impl Other<'_> {
+    /** Get the position of the variant and the stored content, used for ordering. */
+    pub(crate) fn sort_key(&self) -> (u8, &[u8]) {
+        match &self {
+            Other::Comment(event) => (0, event),
+            Other::Text(event) => (1, event),
+            Other::DocType(event) => (2, event),
+            Other::CData(event) => (3, event),
+            Other::Decl(event) => (4, event),
+            Other::PI(event) => (5, event),
+        }
+    }
+}
 
-        let event = self.get_event();
+impl Ord for Other<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
 
-        let write_result = writer.write_event(event);
-        if write_result.is_err() {
-            return Err(write_result.unwrap_err());
-        }
+impl PartialOrd for Other<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        match String::from_utf8(writer.into_inner().into_inner()) {
-            Ok(str) => Ok(str),
-            Err(err) => Err(Error::NonDecodable(Some(err.utf8_error()))),
-        }
+impl ToStringSafe for Other<'_> {
+    fn to_string_safe(&self) -> Result<String, crate::Error> {
+        events_to_string(
+            std::iter::once(self.get_event()),
+            &SerializeOptions::default(),
+        )
     }
 }
 
 impl Display for Other<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = self.to_string_safe().unwrap();
-        write!(f, "{str}")
+        f.write_str(&events_to_string_lossy(self.get_all_events()))
     }
 }
 
 impl GetEvents for Other<'_> {
-    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event> + '_> {
+    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event<'_>> + '_> {
         Box::new(std::iter::once(self.get_event()))
     }
 }
+
+/** Check if a version matches `1.[0-9]+`, like in the `VersionNum` production of the XML specification. */
+fn is_valid_version(version: &str) -> bool {
+    version
+        .strip_prefix("1.")
+        .is_some_and(|minor| !minor.is_empty() && minor.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/** Check if an encoding matches `[A-Za-z][A-Za-z0-9._-]*`, like in the `EncName` production of the XML specification. */
+fn is_valid_encoding(encoding: &str) -> bool {
+    let mut bytes = encoding.bytes();
+    bytes.next().is_some_and(|b| b.is_ascii_alphabetic())
+        && bytes.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/** Check if a string is an XML name.
+
+ASCII characters are checked exactly against the `Name` production of the XML specification.
+Non-ASCII characters are all accepted, which is slightly more lenient than the specification.*/
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| !c.is_ascii() || c.is_ascii_alphabetic() || matches!(c, '_' | ':'))
+        && chars.all(|c| {
+            !c.is_ascii() || c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '-' | '.')
+        })
+}
@@ -0,0 +1,43 @@
+use rayon::prelude::*;
+
+use crate::{Element, Item};
+
+impl Element<'_> {
+    /** Call a function on the element and every element within it, in parallel.
+
+    Only available with the `rayon` feature.
+
+    The subtrees of the children are visited in parallel, so `f` must be `Sync`
+    and the order in which elements are visited is not guaranteed.
+    Every element is still visited exactly once, and never before its parent.
+
+    ```rust
+    # use ilex_xml::*;
+    # use std::sync::atomic::{AtomicUsize, Ordering};
+    let xml = "<a><b><c/></b><d/></a>";
+
+    let Item::Element(element) = &parse(xml)?[0] else {
+        panic!();
+    };
+
+    let count = AtomicUsize::new(0);
+    element.par_visit_elements(|_| {
+        count.fetch_add(1, Ordering::Relaxed);
+    });
+
+    assert_eq!(count.into_inner(), 4);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn par_visit_elements(&self, f: impl Fn(&Element) + Sync) {
+        self.par_visit_elements_inner(&f);
+    }
+
+    fn par_visit_elements_inner(&self, f: &(impl Fn(&Element) + Sync)) {
+        f(self);
+        self.children.par_iter().for_each(|child| {
+            if let Item::Element(element) = child {
+                element.par_visit_elements_inner(f);
+            }
+        });
+    }
+}
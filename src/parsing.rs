@@ -1,24 +1,702 @@
-use crate::{util::qname_to_string, Element, Error, Item, Other, ToStringSafe};
-use quick_xml::{errors::IllFormedError, events::Event, Reader};
+use crate::{
+    serialize::events_to_string,
+    util::{is_xml_whitespace, qname_to_string, GetEvents},
+    Element, Error, Item, Other, SerializeOptions, ToStringSafe, Warning,
+};
+use quick_xml::{
+    errors::IllFormedError,
+    events::{attributes::Attribute, BytesStart, BytesText, Event},
+    name::QName,
+    Reader, Writer,
+};
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    io::{BufRead, Write},
+};
 
-/** Parse raw XML and trim whitespace at the front and end of text. */
-pub fn parse_trimmed(xml: &str) -> Result<Vec<Item>, Error> {
-    let events = read_events(xml, true);
-    Ok(parse_events(events)?)
+/** Parse raw XML and trim whitespace at the front and end of text.
+
+Whitespace within elements marked with `xml:space="preserve"` is not trimmed.
+This is inherited by descendants, unless they are marked with `xml:space="default"`.*/
+pub fn parse_trimmed(xml: &str) -> Result<Vec<Item<'_>>, Error> {
+    let events = read_events(xml.as_bytes(), true);
+    parse_events(events)
+}
+
+/** Parse raw XML.
+
+Adjacent text is always returned as a single text item.
+Entity and character references (like `&amp;`) are part of the surrounding text,
+so text separated only by a reference is a single item as well.
+
+Items keep their source form, including quote styles, whitespace within start tags and entity references.
+Serializing unmodified items with the default options therefore gives back the input byte for byte.
+The only exceptions are whitespace within end tags, like `</a >`, and extra whitespace after `<!DOCTYPE`.
+Editing an item only changes how that item is written, so everything around it stays byte-identical.
+
+```rust
+# use ilex_xml::*;
+let xml = "<doc>\n  <p class='intro'  id=\"1\">A &amp; B</p>\n  <p id=\"2\"/>\n</doc>";
+
+let mut items = parse(xml)?;
+assert_eq!(items_to_string(&items), xml);
+
+let Item::Element(doc) = &mut items[0] else {
+    panic!();
+};
+let Item::Element(p) = &mut doc.children[3] else {
+    panic!();
+};
+p.set_attribute("id", "3");
+
+assert_eq!(items_to_string(&items), xml.replace(r#"id="2""#, r#"id="3""#));
+# Ok::<(), Error>(())
+```*/
+pub fn parse(xml: &str) -> Result<Vec<Item<'_>>, Error> {
+    parse_bytes(xml.as_bytes())
 }
 
-/** Parse raw XML. */
-pub fn parse(xml: &str) -> Result<Vec<Item>, Error> {
+/** Parse raw XML from anything holding a string, like a `String`, `&str` or `Cow<str>`, and own the result.
+
+Unlike [`parse`], the returned items don't borrow from the input, so the input can be passed by value
+and dropped afterwards. This copies the data, so prefer [`parse`] when the input outlives the items anyway.
+
+```rust
+# use ilex_xml::*;
+fn load() -> Result<Vec<Item<'static>>, Error> {
+    let xml = String::from("<a>1</a>");
+    parse_owned(xml)
+}
+
+assert_eq!(items_to_string(&load()?), "<a>1</a>");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_owned(xml: impl AsRef<str>) -> Result<Vec<Item<'static>>, Error> {
+    let events = read_events(xml.as_ref().as_bytes(), false);
+    parse_events(events.map(|event| event.map(Event::into_owned)))
+}
+
+/** Parse raw XML like [`parse_trimmed`], owning the result like [`parse_owned`]. */
+pub fn parse_trimmed_owned(xml: impl AsRef<str>) -> Result<Vec<Item<'static>>, Error> {
+    let events = read_events(xml.as_ref().as_bytes(), true);
+    parse_events(events.map(|event| event.map(Event::into_owned)))
+}
+
+/** Parse raw XML from bytes.
+
+The bytes are not validated as UTF-8 up front.
+Invalid UTF-8 only causes an error once the affected part is decoded, e.g. by `get_name` or `get_value`.*/
+pub fn parse_bytes(xml: &[u8]) -> Result<Vec<Item<'_>>, Error> {
     let events = read_events(xml, false);
-    Ok(parse_events(events)?)
+    parse_events(events)
+}
+
+/** Parse raw XML from bytes, replacing invalid UTF-8 with `U+FFFD`.
+
+This is lossy: every invalid sequence, whether in text, attribute values or names, is replaced
+like [`String::from_utf8_lossy`] does, so the original bytes cannot be written back.
+Use it to recover data from slightly corrupted files. Only well-formedness errors make it fail.
+
+If the bytes are valid UTF-8, nothing is copied and the result is the same as with [`parse_bytes`].
+
+```rust
+# use ilex_xml::*;
+let xml = b"<a title=\"caf\xE9\">na\xEFve</a>";
+
+let Item::Element(element) = &parse_lossy(xml)?[0] else {
+    panic!();
+};
+
+assert_eq!(element.get_attribute("title")?.unwrap(), "caf\u{FFFD}");
+assert_eq!(element.get_text_content(), "na\u{FFFD}ve");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_lossy(xml: &[u8]) -> Result<Vec<Item<'_>>, Error> {
+    match String::from_utf8_lossy(xml) {
+        Cow::Borrowed(xml) => parse(xml),
+        Cow::Owned(xml) => parse_owned(xml),
+    }
+}
+
+/** Options for [`parse_with`]. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /** Turn CDATA sections into escaped text.
+
+    The tree then only contains text items, which are merged with adjacent text.
+    The CDATA form is lost, so ```<![CDATA[a < b]]>``` is written back as `a &lt; b`.*/
+    pub cdata_as_text: bool,
+    /** Keep comments. If disabled, comments are skipped without creating items for them. `true` by default. */
+    pub keep_comments: bool,
+    /** Keep processing instructions. If disabled, they are skipped without creating items for them.
+    The XML declaration is not a processing instruction, so it is always kept. `true` by default.*/
+    pub keep_processing_instructions: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            cdata_as_text: false,
+            keep_comments: true,
+            keep_processing_instructions: true,
+        }
+    }
+}
+
+/** Parse raw XML using the given options.
+
+```rust
+# use ilex_xml::*;
+let options = ParseOptions {
+    cdata_as_text: true,
+    keep_comments: false,
+    ..Default::default()
+};
+
+let items = parse_with("<a>x <!-- note --><![CDATA[< y]]></a>", &options)?;
+
+let Item::Element(element) = &items[0] else {
+    panic!();
+};
+
+assert_eq!(element.children.len(), 1);
+assert_eq!(element.to_string(), "<a>x &lt; y</a>");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_with<'a>(xml: &'a str, options: &ParseOptions) -> Result<Vec<Item<'a>>, Error> {
+    let events = read_events(xml.as_bytes(), false)
+        .filter(|event| match event {
+            Ok(Event::Comment(_)) => options.keep_comments,
+            Ok(Event::PI(_)) => options.keep_processing_instructions,
+            _ => true,
+        })
+        .map(|event| match event? {
+            Event::CData(cdata) if options.cdata_as_text => Ok(Event::Text(cdata.escape()?)),
+            event => Ok(event),
+        });
+    parse_events(events)
+}
+
+/** Statistics about a parsed document. See [`parse_with_report`]. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /** Number of elements. */
+    pub elements: usize,
+    /** Maximum nesting depth of elements. Top level elements have a depth of 1. */
+    pub max_depth: usize,
+    /** Number of well-formed attributes of all elements. */
+    pub attributes: usize,
+    /** Number of malformed attributes, like duplicates or unquoted values.
+
+    Parsing doesn't fail on these, but they are skipped by most attribute getters.*/
+    pub malformed_attributes: usize,
+}
+
+/** Parse raw XML, like [`parse`], and collect statistics about it along the way.
+
+```rust
+# use ilex_xml::*;
+let (items, report) = parse_with_report(r#"<a x="1"><b y="2" y="3"/><c><d/></c></a>"#)?;
+
+assert_eq!(report.elements, 4);
+assert_eq!(report.max_depth, 3);
+assert_eq!(report.attributes, 2);
+assert_eq!(report.malformed_attributes, 1);
+# Ok::<(), Error>(())
+```*/
+pub fn parse_with_report(xml: &str) -> Result<(Vec<Item<'_>>, ParseReport), Error> {
+    let mut report = ParseReport::default();
+    let mut depth = 0;
+
+    let events = read_events(xml.as_bytes(), false).inspect(|event| {
+        let start = match event {
+            Ok(Event::Start(start)) => {
+                depth += 1;
+                report.max_depth = report.max_depth.max(depth);
+                start
+            }
+            Ok(Event::Empty(start)) => {
+                report.max_depth = report.max_depth.max(depth + 1);
+                start
+            }
+            Ok(Event::End(_)) => {
+                depth -= 1;
+                return;
+            }
+            _ => return,
+        };
+        report.elements += 1;
+        for attr in start.attributes() {
+            match attr {
+                Ok(_) => report.attributes += 1,
+                Err(_) => report.malformed_attributes += 1,
+            }
+        }
+    });
+    let items = parse_events(events)?;
+
+    Ok((items, report))
+}
+
+/** Parse raw XML from a buffered reader.
+
+The returned items own their data, so they don't borrow from the reader.
+A `&mut dyn BufRead` is a reader as well, so trait objects can be passed directly.*/
+pub fn parse_reader(reader: impl BufRead) -> Result<Vec<Item<'static>>, Error> {
+    parse_events(ReaderEventIterator::new(reader))
+}
+
+/** Parse raw XML from a buffered reader trait object.
+
+Equivalent to [`parse_reader`], for code that only has an abstract reader at hand.
+
+```rust
+# use ilex_xml::*;
+# use std::io::BufRead;
+let mut input = "<a>1</a><b/>".as_bytes();
+let reader: &mut dyn BufRead = &mut input;
+
+let items = parse_dyn_reader(reader)?;
+
+assert_eq!(items_to_string(&items), "<a>1</a><b/>");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_dyn_reader(reader: &mut dyn BufRead) -> Result<Vec<Item<'static>>, Error> {
+    parse_reader(reader)
+}
+
+/** Parse raw XML made up of several documents, each starting with an XML declaration.
+
+The input is split before every declaration, so every returned list of items starts with one.
+Content before the first declaration is returned as a document of its own,
+unless it is only whitespace, which is dropped.
+The documents are not validated, see [`Document`](crate::Document) for that.
+
+```rust
+# use ilex_xml::*;
+let xml = r#"<?xml version="1.0"?><a>1</a>
+<?xml version="1.0"?><b>2</b>
+"#;
+
+let documents = parse_multi(xml)?;
+
+assert_eq!(documents.len(), 2);
+assert_eq!(items_to_string(&documents[1]), "<?xml version=\"1.0\"?><b>2</b>\n");
+# Ok::<(), Error>(())
+```*/
+pub fn parse_multi(xml: &str) -> Result<Vec<Vec<Item<'_>>>, Error> {
+    let mut documents: Vec<Vec<Item>> = Vec::new();
+
+    for item in parse(xml)? {
+        match documents.last_mut() {
+            Some(document) if !matches!(item, Item::Decl(_)) => document.push(item),
+            _ => documents.push(vec![item]),
+        }
+    }
+
+    if let Some(first) = documents.first() {
+        if first.iter().all(is_whitespace_text) {
+            documents.remove(0);
+        }
+    }
+
+    Ok(documents)
+}
+
+/** An error when parsing a complete document. */
+#[derive(Debug, Clone)]
+pub enum DocumentError {
+    /** The XML could not be parsed. */
+    Xml(Error),
+    /** There is no root element. */
+    MissingRoot,
+    /** There is more than one element at the top level. */
+    MultipleRoots,
+    /** An item is not allowed outside of the root element at its position,
+    e.g. text or a doctype after the root element. */
+    MisplacedItem,
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::Xml(err) => err.fmt(f),
+            DocumentError::MissingRoot => write!(f, "document has no root element"),
+            DocumentError::MultipleRoots => write!(f, "document has more than one root element"),
+            DocumentError::MisplacedItem => {
+                write!(f, "item not allowed outside of the root element")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<Error> for DocumentError {
+    fn from(err: Error) -> Self {
+        DocumentError::Xml(err)
+    }
+}
+
+/** Parse a complete XML document and return its root element.
+
+The document must contain exactly one element at the top level.
+Besides it, only the following items are allowed:
+- The XML declaration, as the very first item.
+- A doctype, before the root element.
+- Comments, processing instructions and whitespace, before or after the root element.
+
+These items are validated, but not returned.
+
+```rust
+# use ilex_xml::*;
+let xml = r#"<?xml version="1.0"?>
+<!-- prolog comment -->
+<html><body/></html>
+<?epilog instruction?>
+"#;
+
+let root = parse_document(xml)?;
+
+assert_eq!(root.get_name().unwrap(), "html");
+# Ok::<(), DocumentError>(())
+```*/
+pub fn parse_document(xml: &str) -> Result<Element<'_>, DocumentError> {
+    let mut items = parse(xml)?;
+    let root = find_root(&items)?;
+    let Item::Element(root) = items.swap_remove(root) else {
+        unreachable!();
+    };
+    Ok(root)
+}
+
+/** Validate the top level items of a document and get the index of the root element. */
+pub(crate) fn find_root(items: &[Item]) -> Result<usize, DocumentError> {
+    let mut root = None;
+
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            Item::Element(_) => {
+                if root.is_some() {
+                    return Err(DocumentError::MultipleRoots);
+                }
+                root = Some(index);
+            }
+            Item::Comment(_) | Item::PI(_) => (),
+            item if is_whitespace_text(item) => (),
+            Item::Decl(_) if index == 0 => (),
+            Item::DocType(_) if root.is_none() => (),
+            _ => return Err(DocumentError::MisplacedItem),
+        }
+    }
+
+    root.ok_or(DocumentError::MissingRoot)
+}
+
+/** An error found while parsing leniently. */
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /** Byte offset in the input at which the error was found. */
+    pub position: u64,
+    /** What went wrong. */
+    pub error: Error,
+}
+
+/** Options for [`parse_lenient_with`]. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LenientOptions {
+    /** Repair malformed attributes where possible. This is not standard XML.
+
+    Attributes are read like in HTML, which accepts:
+    - Unquoted values, like `x=1`.
+    - Attributes without a value, like `disabled`, which get an empty value.
+
+    Repaired attributes are rewritten in double quotes, with `"` and `<` in their values escaped,
+    as well as any `&` which doesn't start an entity or character reference.
+    Attributes that can't be repaired, like duplicates, are dropped.
+    The original errors are still reported, as warnings.*/
+    pub repair_attributes: bool,
+    /** Names of elements which never have content, like HTML void elements. This is not standard XML.
+
+    A start tag with one of these names, like `<br>`, is read as a self-closing element without needing `/>`,
+    and end tags with these names are ignored. Names are compared ASCII-case-insensitively.
+    Empty by default, see [`LenientOptions::HTML_VOID_ELEMENTS`] for the HTML set.*/
+    pub void_elements: Vec<String>,
+}
+
+impl LenientOptions {
+    /** The void elements of HTML: `area`, `base`, `br`, `col`, `embed`, `hr`, `img`, `input`,
+    `link`, `meta`, `source`, `track` and `wbr`. */
+    pub const HTML_VOID_ELEMENTS: [&'static str; 13] = [
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
+        "track", "wbr",
+    ];
+
+    /** Options for parsing HTML fragments: attributes are repaired and HTML void elements are recognized.
+
+    ```rust
+    # use ilex_xml::*;
+    let (items, warnings) = parse_lenient_with("<p>a<br>b<img src=x.png></p>", &LenientOptions::html());
+
+    assert_eq!(items_to_string(&items), r#"<p>a<br/>b<img src="x.png"/></p>"#);
+    assert_eq!(warnings.len(), 1);
+    ```*/
+    pub fn html() -> Self {
+        LenientOptions {
+            repair_attributes: true,
+            void_elements: Self::HTML_VOID_ELEMENTS.map(String::from).to_vec(),
+        }
+    }
+
+    fn is_void(&self, name: QName) -> bool {
+        self.void_elements
+            .iter()
+            .any(|void| void.as_bytes().eq_ignore_ascii_case(name.as_ref()))
+    }
+}
+
+/** Parse raw XML, recovering from as many errors as possible.
+
+Instead of stopping at the first error, every error is recorded with its position and parsing continues:
+- An end tag without a matching start tag is skipped.
+- An element that is not closed is closed implicitly, either by the end tag of an enclosing element or by the end of the input.
+- Elements with malformed attributes are kept as they are.
+- After a syntax error, parsing continues after the malformed part if possible.
+
+The returned tree may therefore be partial.
+
+```rust
+# use ilex_xml::*;
+let (items, errors) = parse_lenient("<a><b></a></c>");
+
+assert_eq!(items_to_string(&items), "<a><b></b></a>");
+assert_eq!(errors.len(), 2);
+```*/
+pub fn parse_lenient(xml: &str) -> (Vec<Item<'_>>, Vec<ParseError>) {
+    parse_lenient_with(xml, &LenientOptions::default())
+}
+
+/** Parse raw XML leniently, like [`parse_lenient`], using the given options.
+
+```rust
+# use ilex_xml::*;
+let options = LenientOptions {
+    repair_attributes: true,
+    ..Default::default()
+};
+
+let (items, warnings) = parse_lenient_with("<input value=1 disabled/>", &options);
+
+assert_eq!(items_to_string(&items), r#"<input value="1" disabled=""/>"#);
+assert_eq!(warnings.len(), 2);
+```*/
+pub fn parse_lenient_with<'a>(
+    xml: &'a str,
+    options: &LenientOptions,
+) -> (Vec<Item<'a>>, Vec<ParseError>) {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().check_end_names = false;
+    reader.config_mut().allow_unmatched_ends = true;
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    // elements which have been started but not ended yet
+    let mut open: Vec<Element> = Vec::new();
+
+    loop {
+        let position = reader.buffer_position();
+
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => event,
+            Err(error) => {
+                errors.push(ParseError {
+                    position: reader.error_position(),
+                    error,
+                });
+                if reader.buffer_position() == position {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let item = match event {
+            Event::Text(text) => {
+                push_text(parent_children(&mut open, &mut items), text);
+                continue;
+            }
+            Event::Start(mut start) if options.is_void(start.name()) => {
+                check_attributes(&mut start, position, options, &mut errors);
+                Item::Element(Element {
+                    element: start,
+                    children: Vec::new(),
+                    self_closing: true,
+                })
+            }
+            Event::Start(mut start) => {
+                check_attributes(&mut start, position, options, &mut errors);
+                open.push(Element {
+                    element: start,
+                    children: Vec::new(),
+                    self_closing: false,
+                });
+                continue;
+            }
+            Event::End(end) if options.is_void(end.name()) => continue,
+            Event::End(end) => {
+                let Some(index) = open
+                    .iter()
+                    .rposition(|element| element.element.name() == end.name())
+                else {
+                    let name = String::from_utf8_lossy(end.name().as_ref()).into_owned();
+                    errors.push(ParseError {
+                        position,
+                        error: Error::IllFormed(IllFormedError::UnmatchedEndTag(name)),
+                    });
+                    continue;
+                };
+                close_elements(&mut open, &mut items, index + 1, position, &mut errors);
+                let Some(element) = open.pop() else {
+                    unreachable!();
+                };
+                Item::Element(element)
+            }
+            Event::Empty(mut empty) => {
+                check_attributes(&mut empty, position, options, &mut errors);
+                Item::Element(Element {
+                    element: empty,
+                    children: Vec::new(),
+                    self_closing: true,
+                })
+            }
+            Event::Comment(item) => Item::Comment(Other::Comment(item)),
+            Event::CData(item) => Item::CData(Other::CData(item)),
+            Event::PI(item) => Item::PI(Other::PI(item)),
+            Event::Decl(item) => Item::Decl(Other::Decl(item)),
+            Event::DocType(item) => Item::DocType(Other::DocType(item)),
+            Event::Eof => unreachable!(),
+        };
+
+        parent_children(&mut open, &mut items).push(item);
+    }
+
+    close_elements(&mut open, &mut items, 0, xml.len() as u64, &mut errors);
+
+    (items, errors)
+}
+
+/** Get the children of the innermost open element, or the top level items if there is none. */
+fn parent_children<'a, 'b>(
+    open: &'b mut [Element<'a>],
+    items: &'b mut Vec<Item<'a>>,
+) -> &'b mut Vec<Item<'a>> {
+    match open.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => items,
+    }
 }
 
-fn parse_events<'a>(mut events: impl Iterator<Item = Result<Event<'a>, Error>>) -> Result<Vec<Item<'a>>, Error> {
+/** Implicitly close open elements until only `keep` are left, recording an error for each. */
+fn close_elements<'a>(
+    open: &mut Vec<Element<'a>>,
+    items: &mut Vec<Item<'a>>,
+    keep: usize,
+    position: u64,
+    errors: &mut Vec<ParseError>,
+) {
+    while open.len() > keep {
+        let Some(element) = open.pop() else {
+            unreachable!();
+        };
+        let name = String::from_utf8_lossy(element.element.name().as_ref()).into_owned();
+        errors.push(ParseError {
+            position,
+            error: Error::IllFormed(IllFormedError::MissingEndTag(name)),
+        });
+        parent_children(open, items).push(Item::Element(element));
+    }
+}
+
+/** Record malformed attributes and repair them if enabled. */
+fn check_attributes(
+    start: &mut BytesStart,
+    position: u64,
+    options: &LenientOptions,
+    errors: &mut Vec<ParseError>,
+) {
+    let mut malformed = false;
+    for attr in start.attributes() {
+        if let Err(err) = attr {
+            malformed = true;
+            errors.push(ParseError {
+                position,
+                error: Error::InvalidAttr(err),
+            });
+        }
+    }
+
+    if !malformed || !options.repair_attributes {
+        return;
+    }
+
+    let repaired: Vec<(Vec<u8>, Vec<u8>)> = start
+        .html_attributes()
+        .filter_map(|attr| attr.ok())
+        .map(|attr| {
+            let mut value = Vec::with_capacity(attr.value.len());
+            for (index, byte) in attr.value.iter().enumerate() {
+                match byte {
+                    b'"' => value.extend_from_slice(b"&quot;"),
+                    b'<' => value.extend_from_slice(b"&lt;"),
+                    b'&' if !starts_with_reference(&attr.value[index + 1..]) => {
+                        value.extend_from_slice(b"&amp;")
+                    }
+                    byte => value.push(*byte),
+                }
+            }
+            (attr.key.as_ref().to_vec(), value)
+        })
+        .collect();
+
+    start.clear_attributes();
+    start.extend_attributes(repaired.iter().map(|(key, value)| Attribute {
+        key: QName(key),
+        value: Cow::Borrowed(value),
+    }));
+}
+
+/** Check if the bytes after a `&` complete an entity or character reference, like `amp;`, `#38;` or `#x26;`. */
+fn starts_with_reference(bytes: &[u8]) -> bool {
+    let Some(length) = bytes.iter().position(|&byte| byte == b';') else {
+        return false;
+    };
+    match &bytes[..length] {
+        [b'#', b'x', digits @ ..] => {
+            !digits.is_empty() && digits.iter().all(|byte| byte.is_ascii_hexdigit())
+        }
+        [b'#', digits @ ..] => {
+            !digits.is_empty() && digits.iter().all(|byte| byte.is_ascii_digit())
+        }
+        [first, rest @ ..] => {
+            (first.is_ascii_alphabetic() || matches!(first, b'_' | b':'))
+                && rest.iter().all(|byte| {
+                    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b':' | b'-' | b'.')
+                })
+        }
+        [] => false,
+    }
+}
+
+fn parse_events<'a>(
+    mut events: impl Iterator<Item = Result<Event<'a>, Error>>,
+) -> Result<Vec<Item<'a>>, Error> {
     let mut items = Vec::new();
 
     while let Some(next) = events.next() {
         match next? {
-            Event::Text(item) => items.push(Item::Text(Other::Text(item.to_owned()))),
+            Event::Text(item) => push_text(&mut items, item),
             Event::Comment(item) => items.push(Item::Comment(Other::Comment(item.to_owned()))),
             Event::CData(item) => items.push(Item::CData(Other::CData(item.to_owned()))),
             Event::PI(item) => items.push(Item::PI(Other::PI(item.to_owned()))),
@@ -36,7 +714,7 @@ fn parse_events<'a>(mut events: impl Iterator<Item = Result<Event<'a>, Error>>)
                     let Some(event_result) = events.next() else {
                         let name = qname_to_string(&start.name());
                         return Err(Error::IllFormed(IllFormedError::MissingEndTag(
-                            name.unwrap_or(String::new()),
+                            name.unwrap_or_default(),
                         )));
                     };
                     let event = event_result?;
@@ -61,13 +739,9 @@ fn parse_events<'a>(mut events: impl Iterator<Item = Result<Event<'a>, Error>>)
                 }));
             }
             Event::End(end) => {
-                let name = qname_to_string(&end.name());
-                if name.is_ok() {
-                    return Err(Error::IllFormed(IllFormedError::UnmatchedEndTag(
-                        name.unwrap(),
-                    )));
-                } else {
-                    return Err(Error::NonDecodable(Some(name.unwrap_err().utf8_error())));
+                return match qname_to_string(&end.name()) {
+                    Ok(name) => Err(Error::IllFormed(IllFormedError::UnmatchedEndTag(name))),
+                    Err(err) => Err(Error::NonDecodable(Some(err.utf8_error()))),
                 };
             }
             Event::Eof => {
@@ -76,45 +750,397 @@ fn parse_events<'a>(mut events: impl Iterator<Item = Result<Event<'a>, Error>>)
         }
     }
 
-    return Ok(items);
+    Ok(items)
+}
+
+/** Check if an item is text made up of XML whitespace only. */
+fn is_whitespace_text(item: &Item) -> bool {
+    let Item::Text(Other::Text(text)) = item else {
+        return false;
+    };
+    text.iter().copied().all(is_xml_whitespace)
+}
+
+/** Push a text item, merging it into the previous item if that is text as well.
+
+Text that is not valid UTF-8 is never merged.*/
+fn push_text<'a>(items: &mut Vec<Item<'a>>, text: BytesText<'a>) {
+    if let Some(Item::Text(Other::Text(previous))) = items.last_mut() {
+        if let (Ok(first), Ok(second)) = (std::str::from_utf8(previous), std::str::from_utf8(&text))
+        {
+            *previous = BytesText::from_escaped(format!("{first}{second}"));
+            return;
+        }
+    }
+    items.push(Item::Text(Other::Text(text)));
 }
 
 struct EventIterator<'a> {
-    reader: Reader<&'a [u8]>
+    reader: Reader<&'a [u8]>,
+    /** Whether text should be trimmed outside of `xml:space="preserve"`. */
+    trim: bool,
+    /** For every open element: Is whitespace preserved within it? */
+    preserve_stack: Vec<bool>,
 }
 
 impl<'a> Iterator for EventIterator<'a> {
     type Item = Result<Event<'a>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.reader.read_event() {
+        let event = match self.reader.read_event() {
+            Err(err) => return Some(Err(err)),
+
+            Ok(Event::Eof) => return None,
+
+            Ok(e) => e,
+        };
+
+        if self.trim {
+            self.update_trimming(&event);
+        }
+
+        Some(Ok(event))
+    }
+}
+
+impl EventIterator<'_> {
+    /** Track `xml:space` and only trim text where whitespace is not preserved. */
+    fn update_trimming(&mut self, event: &Event) {
+        match event {
+            Event::Start(start) => {
+                let inherited = self.preserve_stack.last().copied().unwrap_or(false);
+                let preserve = match start.try_get_attribute("xml:space") {
+                    Ok(Some(attr)) if attr.value.as_ref() == b"preserve" => true,
+                    Ok(Some(attr)) if attr.value.as_ref() == b"default" => false,
+                    _ => inherited,
+                };
+                self.preserve_stack.push(preserve);
+            }
+            Event::End(_) => {
+                self.preserve_stack.pop();
+            }
+            _ => return,
+        }
+
+        let preserve = self.preserve_stack.last().copied().unwrap_or(false);
+        self.reader.config_mut().trim_text(!preserve);
+    }
+}
+
+fn read_events(xml: &[u8], trim: bool) -> impl Iterator<Item = Result<Event<'_>, Error>> {
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(trim);
+    EventIterator {
+        reader,
+        trim,
+        preserve_stack: Vec::new(),
+    }
+}
+
+/** Reads owned events from a buffered reader. */
+struct ReaderEventIterator<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> ReaderEventIterator<R> {
+    fn new(reader: R) -> Self {
+        ReaderEventIterator {
+            reader: Reader::from_reader(reader),
+            buf: Vec::new(),
+        }
+    }
+
+    /** Read the events of the next top level item.
+
+    For an element, this includes all events up to and including its end tag.*/
+    fn next_item_events(&mut self) -> Result<Option<Vec<Event<'static>>>, Error> {
+        let mut events = Vec::new();
+        let mut depth = 0;
+
+        for event in self.by_ref() {
+            let event = event?;
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => (),
+            }
+            events.push(event);
+            if depth <= 0 {
+                return Ok(Some(events));
+            }
+        }
+
+        // an unclosed element is reported when parsing the events
+        if events.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(events))
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderEventIterator<R> {
+    type Item = Result<Event<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buf.clear();
+        match self.reader.read_event_into(&mut self.buf) {
             Err(err) => Some(Err(err)),
 
             Ok(Event::Eof) => None,
 
-            Ok(e) => Some(Ok(e)),
+            Ok(e) => Some(Ok(e.into_owned())),
         }
     }
 }
 
-fn read_events(xml: &str, trim: bool) -> impl Iterator<Item = Result<Event, Error>> {
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(trim);
-    EventIterator { reader }
+/** Parse only the first element with the given name from a reader, at any depth.
+
+Events before the element are skipped without building items, and reading stops right after its end tag,
+so the rest of the input is never parsed. Useful for pulling a header out of a huge document.
+Returns `None` if there is no such element.
+
+```rust
+# use ilex_xml::*;
+let input = "<feed><metadata><title>News</title></metadata><entry>...</entry><unfinished";
+
+let metadata = extract_first(input.as_bytes(), "metadata")?.unwrap();
+
+assert_eq!(metadata.to_string(), "<metadata><title>News</title></metadata>");
+# Ok::<(), Error>(())
+```*/
+pub fn extract_first(reader: impl BufRead, name: &str) -> Result<Option<Element<'static>>, Error> {
+    let mut events = ReaderEventIterator::new(reader);
+
+    while let Some(event) = events.next() {
+        match event? {
+            Event::Empty(start) if start.name().as_ref() == name.as_bytes() => {
+                return Ok(Some(Element {
+                    element: start,
+                    children: Vec::new(),
+                    self_closing: true,
+                }));
+            }
+            Event::Start(start) if start.name().as_ref() == name.as_bytes() => {
+                let mut element_events = vec![Event::Start(start)];
+                let mut depth = 1;
+                for event in events.by_ref() {
+                    let event = event?;
+                    match event {
+                        Event::Start(_) => depth += 1,
+                        Event::End(_) => depth -= 1,
+                        _ => (),
+                    }
+                    element_events.push(event);
+                    if depth == 0 {
+                        break;
+                    }
+                }
+
+                // an unclosed element is reported when parsing the events
+                let Some(Item::Element(element)) =
+                    parse_events(element_events.into_iter().map(Ok))?.pop()
+                else {
+                    unreachable!();
+                };
+                return Ok(Some(element));
+            }
+            _ => (),
+        }
+    }
+
+    Ok(None)
+}
+
+/** Copy XML from a reader to a writer, dropping the top level items not matching the predicate.
+
+Only one top level item is held in memory at a time, so this is suitable for huge documents made up of many small items.
+The predicate is called once for every top level item, including whitespace text.
+
+```rust
+# use ilex_xml::*;
+let input = "<item new=\"yes\">1</item><item>2</item><item new=\"yes\">3</item>";
+
+let mut output = Vec::new();
+filter_stream(input.as_bytes(), &mut output, |item| match item {
+    Item::Element(element) => element.has_attribute("new"),
+    _ => true,
+})?;
+
+assert_eq!(output, b"<item new=\"yes\">1</item><item new=\"yes\">3</item>");
+# Ok::<(), Error>(())
+```*/
+pub fn filter_stream(
+    reader: impl BufRead,
+    writer: impl Write,
+    mut keep: impl FnMut(&Item) -> bool,
+) -> Result<(), Error> {
+    let mut events = ReaderEventIterator::new(reader);
+    let mut writer = Writer::new(writer);
+
+    while let Some(item_events) = events.next_item_events()? {
+        for item in parse_events(item_events.into_iter().map(Ok))? {
+            if !keep(&item) {
+                continue;
+            }
+            for event in item.get_all_events() {
+                writer.write_event(event)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/** Make sure a list of items starts with an XML declaration.
+
+If the first item is not a declaration, `<?xml version="1.0" encoding="UTF-8"?>` is inserted in front of the items.
+
+```rust
+# use ilex_xml::*;
+let mut items = parse("<a/>")?;
+
+ensure_declaration(&mut items);
+ensure_declaration(&mut items);
+
+assert_eq!(items_to_string(&items), r#"<?xml version="1.0" encoding="UTF-8"?><a/>"#);
+# Ok::<(), Error>(())
+```*/
+pub fn ensure_declaration(items: &mut Vec<Item>) {
+    if !matches!(items.first(), Some(Item::Decl(_))) {
+        items.insert(0, Item::new_decl_unchecked("1.0", Some("UTF-8"), None));
+    }
+}
+
+/** Remove all XML declarations from a list of items, e.g. to embed them in another document.
+
+Only top level items are checked, since declarations can't occur within elements.
+
+```rust
+# use ilex_xml::*;
+let mut items = parse(r#"<?xml version="1.0"?><a/>"#)?;
+
+strip_declaration(&mut items);
+
+assert_eq!(items_to_string(&items), "<a/>");
+# Ok::<(), Error>(())
+```*/
+pub fn strip_declaration(items: &mut Vec<Item>) {
+    items.retain(|item| !matches!(item, Item::Decl(_)));
 }
 
 /** Stringify a list of XML items.
 
 Equivalent to calling `to_string` on each item and concatenating the results.
+Accepts anything that iterates over item references, such as a slice or a filtered iterator.
 
-Parsing errors are silently ignored.*/
-pub fn items_to_string(items: &[Item]) -> String {
-    items
-        .iter()
-        .map(|item| item.to_string_safe())
-        .filter_map(|result| match result {
-            Ok(str) => Some(str),
-            Err(_) => None,
-        })
-        .collect()
+Parsing errors are silently ignored, see [`items_to_string_with_warnings`] to detect them.*/
+pub fn items_to_string<'a: 'b, 'b>(items: impl IntoIterator<Item = &'b Item<'a>>) -> String {
+    items_to_string_with_warnings(items).0
+}
+
+/** Stringify a list of XML items like [`items_to_string`], along with a warning for every item that was skipped.
+
+```rust
+# use ilex_xml::*;
+let items = parse_bytes(b"<a/><b>\xFF</b><c/>")?;
+
+let (xml, warnings) = items_to_string_with_warnings(&items);
+
+assert_eq!(xml, "<a/><c/>");
+assert!(matches!(warnings[..], [Warning::Item { index: 1, .. }]));
+# Ok::<(), Error>(())
+```*/
+pub fn items_to_string_with_warnings<'a: 'b, 'b>(
+    items: impl IntoIterator<Item = &'b Item<'a>>,
+) -> (String, Vec<Warning>) {
+    let mut xml = String::new();
+    let mut warnings = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match item.to_string_safe() {
+            Ok(item) => xml.push_str(&item),
+            Err(error) => warnings.push(Warning::Item { index, error }),
+        }
+    }
+    (xml, warnings)
+}
+
+/** Stringify a list of XML items using the given options.
+
+Unlike [`items_to_string`], the items are serialized together, so options like indentation apply across them,
+and errors are not ignored.
+
+```rust
+# use ilex_xml::*;
+let items = parse(r#"<?xml version="1.0"?><!DOCTYPE root><!--generated--><root><a/></root>"#)?;
+
+let options = SerializeOptions {
+    top_level_blank_lines: true,
+    ..SerializeOptions::pretty("  ")
+};
+
+assert_eq!(
+    items_to_string_with(&items, &options)?,
+    "<?xml version=\"1.0\"?>\n\n<!DOCTYPE root>\n\n<!--generated-->\n\n<root>\n  <a/>\n</root>"
+);
+# Ok::<(), Error>(())
+```*/
+pub fn items_to_string_with<'a: 'b, 'b>(
+    items: impl IntoIterator<Item = &'b Item<'a>>,
+    options: &SerializeOptions,
+) -> Result<String, Error> {
+    events_to_string(
+        items.into_iter().flat_map(|item| item.get_all_events()),
+        options,
+    )
+}
+
+/** Serialize a list of XML items to bytes.
+
+Like [`items_to_string`], but returns the written bytes as they are, without checking them for UTF-8 validity.
+Useful when the output is written to a file or socket anyway.
+
+```rust
+# use ilex_xml::*;
+let items = parse("<a>1</a><b/>")?;
+
+assert_eq!(items_to_bytes(&items), b"<a>1</a><b/>");
+# Ok::<(), Error>(())
+```*/
+pub fn items_to_bytes<'a: 'b, 'b>(items: impl IntoIterator<Item = &'b Item<'a>>) -> Vec<u8> {
+    let mut output = Vec::new();
+    write_items(&mut output, items).expect("writing to a Vec never fails");
+    output
+}
+
+/** Write a list of XML items to a writer.
+
+Unlike [`items_to_string`], the items are streamed into the writer without building a `String` first,
+and errors are not ignored. The written bytes are not checked for UTF-8 validity,
+so the only possible error is `Error::Io` from the writer.
+
+```rust
+# use ilex_xml::*;
+let items = parse("<a/>text")?;
+
+let mut output = Vec::new();
+write_items(&mut output, &items)?;
+
+assert_eq!(output, b"<a/>text");
+# Ok::<(), Error>(())
+```*/
+pub fn write_items<'a: 'b, 'b>(
+    writer: impl Write,
+    items: impl IntoIterator<Item = &'b Item<'a>>,
+) -> Result<(), Error> {
+    let mut writer = Writer::new(writer);
+
+    for item in items {
+        for event in item.get_all_events() {
+            writer.write_event(event)?;
+        }
+    }
+
+    Ok(())
 }
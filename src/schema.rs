@@ -0,0 +1,143 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{Element, Item};
+
+/** A simple description of which elements may contain what.
+
+Elements are described by their tag name. Elements without a description are not checked.
+This is far simpler than XSD or RELAX NG, but covers common checks of configuration files.
+
+```rust
+# use ilex_xml::*;
+let mut schema = Schema::default();
+schema.elements.insert(
+    String::from("config"),
+    ElementSchema {
+        allowed_children: Some(vec![String::from("entry")]),
+        ..Default::default()
+    },
+);
+schema.elements.insert(
+    String::from("entry"),
+    ElementSchema {
+        required_attributes: vec![String::from("key")],
+        ..Default::default()
+    },
+);
+
+let root = parse_document(r#"<config><entry key="a"/><entry/><other/></config>"#).unwrap();
+
+let errors = validate_tree(&root, &schema).unwrap_err();
+
+assert_eq!(errors[0].path, "/config/entry[2]");
+assert_eq!(errors[0].kind, ValidationErrorKind::MissingAttribute(String::from("key")));
+assert_eq!(errors[1].path, "/config/other");
+assert_eq!(errors[1].kind, ValidationErrorKind::UnexpectedChild(String::from("other")));
+```*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    /** The descriptions of elements by tag name. */
+    pub elements: HashMap<String, ElementSchema>,
+}
+
+/** The description of an element in a [`Schema`]. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementSchema {
+    /** Tag names of the child elements allowed within the element.
+
+    If `None`, any child elements are allowed. Items other than elements are always allowed.*/
+    pub allowed_children: Option<Vec<String>>,
+    /** Keys of the attributes the element must have. */
+    pub required_attributes: Vec<String>,
+}
+
+/** A violation of a [`Schema`]. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /** Path of the offending element, as returned by [`Element::format_path`]. */
+    pub path: String,
+    /** What is wrong with the element. */
+    pub kind: ValidationErrorKind,
+}
+
+/** What is wrong with an element. See [`ValidationError`]. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /** The element is not allowed within its parent. Holds the tag name of the element. */
+    UnexpectedChild(String),
+    /** The element lacks a required attribute. Holds the key of the attribute. */
+    MissingAttribute(String),
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ValidationErrorKind::UnexpectedChild(name) => {
+                write!(f, "{}: unexpected child element {name}", self.path)
+            }
+            ValidationErrorKind::MissingAttribute(key) => {
+                write!(f, "{}: missing attribute {key}", self.path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/** Check an element and all elements within it against a schema.
+
+Returns all violations in document order.*/
+pub fn validate_tree(root: &Element, schema: &Schema) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    validate_element(root, root, &mut Vec::new(), schema, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_element(
+    root: &Element,
+    element: &Element,
+    path: &mut Vec<usize>,
+    schema: &Schema,
+    errors: &mut Vec<ValidationError>,
+) {
+    let description = std::str::from_utf8(element.element.name().into_inner())
+        .ok()
+        .and_then(|name| schema.elements.get(name));
+
+    if let Some(description) = description {
+        for key in &description.required_attributes {
+            if !element.has_attribute(key) {
+                errors.push(ValidationError {
+                    path: root.format_path(path).unwrap_or_default(),
+                    kind: ValidationErrorKind::MissingAttribute(key.clone()),
+                });
+            }
+        }
+    }
+
+    for (index, child) in element.children.iter().enumerate() {
+        let Item::Element(child) = child else {
+            continue;
+        };
+        path.push(index);
+
+        if let Some(allowed) = description.and_then(|d| d.allowed_children.as_ref()) {
+            if !allowed.iter().any(|name| child.name_equals(name)) {
+                errors.push(ValidationError {
+                    path: root.format_path(path).unwrap_or_default(),
+                    kind: ValidationErrorKind::UnexpectedChild(
+                        String::from_utf8_lossy(child.element.name().as_ref()).into_owned(),
+                    ),
+                });
+            }
+        }
+
+        validate_element(root, child, path, schema, errors);
+        path.pop();
+    }
+}
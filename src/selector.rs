@@ -0,0 +1,194 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::Element;
+
+/** A simple CSS selector matching a single element.
+
+Supported are a tag name or `*`, followed by any number of:
+- `#id` to match the `id` attribute.
+- `.class` to match one of the whitespace separated classes in the `class` attribute.
+- `[attr]` to require an attribute.
+- `[attr=value]` or `[attr="value"]` to match the unescaped value of an attribute.
+
+Names may contain namespace prefixes like `svg:rect`.
+Combinators, selector lists and pseudo-classes are not supported,
+since a single element doesn't know about its ancestors or siblings.
+
+```rust
+# use ilex_xml::*;
+let selector: Selector = "rect.visible[fill=red]".parse()?;
+
+let Item::Element(element) = &parse(r#"<rect class="big visible" fill="red"/>"#).unwrap()[0] else {
+    panic!();
+};
+
+assert!(element.matches(&selector));
+# Ok::<(), SelectorError>(())
+```*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    name: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Id(String),
+    Class(String),
+    Has(String),
+    Equals(String, String),
+}
+
+/** An error when parsing a selector. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    /** Byte offset in the selector at which the error was found. */
+    pub position: usize,
+}
+
+impl Display for SelectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid selector at position {}", self.position)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let mut parser = SelectorParser {
+            selector: selector.trim(),
+            position: 0,
+        };
+
+        let universal = parser.eat('*');
+        let name = if universal {
+            None
+        } else {
+            match parser.name() {
+                "" => None,
+                name => Some(String::from(name)),
+            }
+        };
+
+        let mut conditions = Vec::new();
+        while let Some(next) = parser.peek() {
+            let start = parser.position;
+            parser.position += next.len_utf8();
+            let condition = match next {
+                '#' => Condition::Id(String::from(parser.required_name()?)),
+                '.' => Condition::Class(String::from(parser.required_name()?)),
+                '[' => {
+                    let key = String::from(parser.required_name()?);
+                    let condition = if parser.eat('=') {
+                        Condition::Equals(key, String::from(parser.value()?))
+                    } else {
+                        Condition::Has(key)
+                    };
+                    if !parser.eat(']') {
+                        return Err(parser.error());
+                    }
+                    condition
+                }
+                _ => return Err(SelectorError { position: start }),
+            };
+            conditions.push(condition);
+        }
+
+        if !universal && name.is_none() && conditions.is_empty() {
+            return Err(parser.error());
+        }
+
+        Ok(Selector { name, conditions })
+    }
+}
+
+struct SelectorParser<'s> {
+    selector: &'s str,
+    position: usize,
+}
+
+impl<'s> SelectorParser<'s> {
+    fn peek(&self) -> Option<char> {
+        self.selector[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self) -> SelectorError {
+        SelectorError {
+            position: self.position,
+        }
+    }
+
+    fn name(&mut self) -> &'s str {
+        let rest = &self.selector[self.position..];
+        let length = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | ':')))
+            .unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn required_name(&mut self) -> Result<&'s str, SelectorError> {
+        match self.name() {
+            "" => Err(self.error()),
+            name => Ok(name),
+        }
+    }
+
+    fn value(&mut self) -> Result<&'s str, SelectorError> {
+        let Some(quote) = self.peek().filter(|c| matches!(c, '"' | '\'')) else {
+            return self.required_name();
+        };
+        let rest = &self.selector[self.position + 1..];
+        let Some(length) = rest.find(quote) else {
+            return Err(self.error());
+        };
+        self.position += length + 2;
+        Ok(&rest[..length])
+    }
+}
+
+impl Element<'_> {
+    /** Check if the element matches the selector.
+
+    Doesn't allocate unless an attribute value compared by the selector contains escaped characters.*/
+    pub fn matches(&self, selector: &Selector) -> bool {
+        if let Some(name) = &selector.name {
+            if !self.name_equals(name) {
+                return false;
+            }
+        }
+
+        selector.conditions.iter().all(|condition| match condition {
+            Condition::Id(id) => self.attribute_matches("id", |value| value == id),
+            Condition::Class(class) => self.attribute_matches("class", |value| {
+                value.split_ascii_whitespace().any(|c| c == class)
+            }),
+            Condition::Has(key) => self.has_attribute(key),
+            Condition::Equals(key, expected) => {
+                self.attribute_matches(key, |value| value == expected)
+            }
+        })
+    }
+
+    fn attribute_matches(&self, key: &str, predicate: impl Fn(&str) -> bool) -> bool {
+        let Ok(Some(attr)) = self.element.try_get_attribute(key) else {
+            return false;
+        };
+        let Ok(value) = attr.unescape_value() else {
+            return false;
+        };
+        predicate(&value)
+    }
+}
@@ -0,0 +1,261 @@
+use quick_xml::{
+    events::{attributes::Attribute, BytesStart, BytesText, Event},
+    Writer,
+};
+
+use crate::{util::escape_quotes, Error};
+
+/** Options for turning items into XML.
+
+The default options write the items exactly as they are, without adding any whitespace.
+
+```rust
+# use ilex_xml::*;
+let xml = "<table><tr><td>1</td><td>a long cell</td></tr></table>";
+
+let Item::Element(element) = &parse(xml)?[0] else {
+    panic!();
+};
+
+let options = SerializeOptions {
+    indent: Some(String::from("  ")),
+    inline_text_limit: Some(5),
+    ..Default::default()
+};
+
+assert_eq!(
+    element.to_string_with(&options)?,
+    "<table>\n  <tr>\n    <td>1</td>\n    <td>\n      a long cell\n    </td>\n  </tr>\n</table>"
+);
+# Ok::<(), Error>(())
+```*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /** Indentation to use once per level of depth.
+
+    If set, every tag, comment and similar item is put on its own line.
+    Text is kept inline, so whitespace in mixed content is not changed.
+    For the same reason, a comment within an element that is followed by text stays inline.*/
+    pub indent: Option<String>,
+    /** Maximum length in bytes of a text to keep inline when indenting.
+
+    An element whose only child is a longer text is spread over multiple lines,
+    with the text on its own line. This adds whitespace around the text.
+    If not set, text is always kept inline.*/
+    pub inline_text_limit: Option<usize>,
+    /** Put an empty line between top level items when indenting.
+
+    Separates the declaration, doctype, comments and the root element of a document into sections.*/
+    pub top_level_blank_lines: bool,
+    /** Line endings to use in the output.
+
+    Applies to every line break in the output, both the ones added by indentation and the ones
+    within text, comments, CDATA sections and attribute values.
+    Line breaks written as character references, like `&#13;`, are not affected.*/
+    pub line_ending: LineEnding,
+    /** Write the attributes of every element sorted by key, bytewise.
+
+    Namespace declarations (`xmlns` and `xmlns:*`) are kept in front of all other attributes,
+    so the declarations an element relies on are still written first. They are sorted among themselves as well.
+    Attributes with the same key keep their relative order.
+    Sorted attributes are written in double quotes, separated by a single space.*/
+    pub sort_attributes: bool,
+}
+
+/** Line endings to use when serializing. See [`SerializeOptions::line_ending`]. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /** Keep line breaks as they are. Line breaks added by indentation are written as `\n`. */
+    #[default]
+    AsSource,
+    /** Write every line break, including `\r\n` and a lone `\r`, as `\n`. */
+    Lf,
+    /** Write every line break, including a lone `\n` or `\r`, as `\r\n`. */
+    CrLf,
+}
+
+impl SerializeOptions {
+    /** Options for indenting with the given string. */
+    pub fn pretty(indent: &str) -> Self {
+        SerializeOptions {
+            indent: Some(String::from(indent)),
+            ..Default::default()
+        }
+    }
+
+    /** Options for indenting with a character repeated `indent_size` times per level.
+
+    Returns `None` if the character is not XML whitespace (space, tab, carriage return or line feed),
+    since anything else would change the content of the document.
+
+    ```rust
+    # use ilex_xml::*;
+    let Item::Element(element) = &parse("<a><b/></a>")?[0] else {
+        panic!();
+    };
+
+    let options = SerializeOptions::indented(b'\t', 2).unwrap();
+    assert_eq!(element.to_string_with(&options)?, "<a>\n\t\t<b/>\n</a>");
+
+    assert!(SerializeOptions::indented(b'-', 2).is_none());
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn indented(indent_char: u8, indent_size: usize) -> Option<Self> {
+        if !matches!(indent_char, b' ' | b'\t' | b'\r' | b'\n') {
+            return None;
+        }
+        Some(SerializeOptions {
+            indent: Some(String::from(indent_char as char).repeat(indent_size)),
+            ..Default::default()
+        })
+    }
+}
+
+/** Serialize events to a string. */
+pub(crate) fn events_to_string<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    options: &SerializeOptions,
+) -> Result<String, Error> {
+    let mut writer = Writer::new(Vec::new());
+
+    let events = events.map(|event| match event {
+        Event::Start(start) if options.sort_attributes => Event::Start(sort_attributes(start)),
+        Event::Empty(start) if options.sort_attributes => Event::Empty(sort_attributes(start)),
+        event => event,
+    });
+
+    let Some(indent) = &options.indent else {
+        for event in events {
+            writer.write_event(event)?;
+        }
+        return into_string(writer, options.line_ending);
+    };
+
+    let mut events = events.peekable();
+
+    let mut depth = 0;
+    // whether the next tag should be put on a new line
+    let mut line_break = false;
+    // whether the previous event was a start tag
+    let mut after_start = false;
+
+    while let Some(event) = events.next() {
+        let (breaks_line, depth_change) = match &event {
+            Event::Start(_) => (line_break, 1),
+            Event::End(_) => {
+                depth -= 1;
+                (line_break && !after_start, 0)
+            }
+            Event::Text(_) | Event::CData(_) => (false, 0),
+            // a comment followed by text is part of mixed content, which is not reflowed
+            Event::Comment(_) if depth > 0 => (
+                line_break && !matches!(events.peek(), Some(Event::Text(_) | Event::CData(_))),
+                0,
+            ),
+            _ => (line_break, 0),
+        };
+
+        if breaks_line {
+            let top_level = depth == 0 && !matches!(event, Event::End(_));
+            if top_level && options.top_level_blank_lines {
+                writer.write_event(Event::Text(BytesText::from_escaped("\n")))?;
+            }
+            write_line_break(&mut writer, indent, depth)?;
+        }
+
+        line_break = !matches!(event, Event::Text(_) | Event::CData(_));
+        after_start = matches!(event, Event::Start(_));
+        depth += depth_change;
+
+        writer.write_event(event)?;
+
+        if !after_start {
+            continue;
+        }
+        let Some(limit) = options.inline_text_limit else {
+            continue;
+        };
+        let Some(Event::Text(text)) = events.peek() else {
+            continue;
+        };
+        if text.len() <= limit {
+            continue;
+        }
+
+        // put a long text on its own line, if it is the only child
+        let Some(text) = events.next() else {
+            unreachable!();
+        };
+        let only_child = matches!(events.peek(), Some(Event::End(_)));
+        if only_child {
+            write_line_break(&mut writer, indent, depth)?;
+        }
+        writer.write_event(text)?;
+        after_start = false;
+        line_break = only_child;
+    }
+
+    into_string(writer, options.line_ending)
+}
+
+/** Serialize events to a string, replacing invalid UTF-8 with `U+FFFD`, so it never fails. */
+pub(crate) fn events_to_string_lossy<'a>(events: impl Iterator<Item = Event<'a>>) -> String {
+    let mut writer = Writer::new(Vec::new());
+    for event in events {
+        writer
+            .write_event(event)
+            .expect("writing to a Vec never fails");
+    }
+    String::from_utf8(writer.into_inner())
+        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned())
+}
+
+/** Sort the attributes of a tag, see [`SerializeOptions::sort_attributes`].
+
+Tags with malformed attributes are returned unchanged.*/
+fn sort_attributes(start: BytesStart) -> BytesStart {
+    let Ok(mut attributes) = start
+        .attributes()
+        .with_checks(false)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return start;
+    };
+    attributes.sort_by(|a, b| {
+        let a = (a.key.as_namespace_binding().is_none(), a.key.as_ref());
+        let b = (b.key.as_namespace_binding().is_none(), b.key.as_ref());
+        a.cmp(&b)
+    });
+
+    let mut sorted = start.to_owned();
+    sorted.clear_attributes();
+    sorted.extend_attributes(attributes.iter().map(|attr| Attribute {
+        key: attr.key,
+        value: escape_quotes(&attr.value),
+    }));
+    sorted
+}
+
+fn write_line_break(writer: &mut Writer<Vec<u8>>, indent: &str, depth: usize) -> Result<(), Error> {
+    let whitespace = format!("\n{}", indent.repeat(depth));
+    writer.write_event(Event::Text(BytesText::from_escaped(whitespace)))
+}
+
+fn into_string(writer: Writer<Vec<u8>>, line_ending: LineEnding) -> Result<String, Error> {
+    match String::from_utf8(writer.into_inner()) {
+        Ok(str) => Ok(normalize_line_endings(str, line_ending)),
+        Err(err) => Err(Error::NonDecodable(Some(err.utf8_error()))),
+    }
+}
+
+fn normalize_line_endings(str: String, line_ending: LineEnding) -> String {
+    match line_ending {
+        LineEnding::AsSource => str,
+        LineEnding::Lf if !str.contains('\r') => str,
+        LineEnding::Lf => str.replace("\r\n", "\n").replace('\r', "\n"),
+        LineEnding::CrLf => str
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .replace('\n', "\r\n"),
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use quick_xml::{
+    errors::IllFormedError,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    Writer,
+};
+
+use crate::{Error, GetEvents, Item};
+
+/** Writes XML incrementally, without holding the document in memory.
+
+The writer keeps track of the open elements, so end tags never need a name and the document is always balanced.
+
+```rust
+# use ilex_xml::*;
+let mut writer = StreamWriter::new(Vec::new());
+
+writer.start_element("list", &[("size", "2")])?;
+for i in 0..2 {
+    writer.start_element("item", &[])?;
+    writer.text(&format!("{i} < 2"))?;
+    writer.end_element()?;
+}
+writer.empty_element("end", &[])?;
+writer.end_element()?;
+
+let output = writer.finish()?;
+
+assert_eq!(
+    output,
+    br#"<list size="2"><item>0 &lt; 2</item><item>1 &lt; 2</item><end/></list>"#
+);
+# Ok::<(), Error>(())
+```*/
+pub struct StreamWriter<W: Write> {
+    writer: Writer<W>,
+    /** Names of the elements which have been started but not ended yet. */
+    open: Vec<String>,
+}
+
+impl<W: Write> StreamWriter<W> {
+    /** Create a stream writer writing to the given writer. */
+    pub fn new(writer: W) -> Self {
+        StreamWriter {
+            writer: Writer::new(writer),
+            open: Vec::new(),
+        }
+    }
+
+    /** Write a start tag. Attribute values are escaped. */
+    pub fn start_element(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<(), Error> {
+        let start = BytesStart::new(name).with_attributes(attributes.iter().copied());
+        self.writer.write_event(Event::Start(start))?;
+        self.open.push(String::from(name));
+        Ok(())
+    }
+
+    /** Write a self-closing element. Attribute values are escaped. */
+    pub fn empty_element(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<(), Error> {
+        let start = BytesStart::new(name).with_attributes(attributes.iter().copied());
+        self.writer.write_event(Event::Empty(start))
+    }
+
+    /** Write text. The text is escaped. */
+    pub fn text(&mut self, text: &str) -> Result<(), Error> {
+        self.writer.write_event(Event::Text(BytesText::new(text)))
+    }
+
+    /** Write an item, including all of its descendants. */
+    pub fn item(&mut self, item: &Item) -> Result<(), Error> {
+        for event in item.get_all_events() {
+            self.writer.write_event(event)?;
+        }
+        Ok(())
+    }
+
+    /** Write the end tag of the innermost open element.
+
+    Fails with `Error::IllFormed` if there is no open element.*/
+    pub fn end_element(&mut self) -> Result<(), Error> {
+        let Some(name) = self.open.pop() else {
+            return Err(Error::IllFormed(IllFormedError::UnmatchedEndTag(
+                String::new(),
+            )));
+        };
+        self.writer.write_event(Event::End(BytesEnd::new(name)))
+    }
+
+    /** Get the number of elements which have been started but not ended yet. */
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /** Finish writing and return the underlying writer.
+
+    Fails with `Error::IllFormed` if an element has not been ended.*/
+    pub fn finish(self) -> Result<W, Error> {
+        if let Some(name) = self.open.last() {
+            return Err(Error::IllFormed(IllFormedError::MissingEndTag(
+                name.clone(),
+            )));
+        }
+        Ok(self.writer.into_inner())
+    }
+}
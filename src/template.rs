@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, BytesText},
+};
+
+use crate::{util::attribute_spans, Element, Item, Other};
+
+/** Create a copy of a template with all placeholders filled in.
+
+Placeholders are written as `{{name}}` in text and attribute values.
+Whitespace around the name is ignored, so `{{ name }}` works as well.
+Values are escaped before they are inserted.
+Placeholders referring to a variable that is not in `vars` are left as they are.
+Only the attribute values containing a placeholder are rewritten; the rest of each tag is kept exactly as written.
+
+```rust
+# use ilex_xml::*;
+# use std::collections::HashMap;
+let xml = r#"<greeting lang="{{lang}}">Hello {{ name }}! {{unknown}}</greeting>"#;
+
+let Item::Element(template) = &parse(xml)?[0] else {
+    panic!();
+};
+
+let vars = HashMap::from([
+    (String::from("lang"), String::from("en")),
+    (String::from("name"), String::from("Tom & Jerry")),
+]);
+
+assert_eq!(
+    instantiate(template, &vars).to_string(),
+    r#"<greeting lang="en">Hello Tom &amp; Jerry! {{unknown}}</greeting>"#
+);
+# Ok::<(), Error>(())
+```*/
+pub fn instantiate<'a>(template: &Element<'a>, vars: &HashMap<String, String>) -> Element<'a> {
+    let mut element = template.clone();
+
+    element.visit_elements_mut(true, |element| {
+        substitute_attributes(element, vars);
+
+        for child in element.children.iter_mut() {
+            let Item::Text(Other::Text(text)) = child else {
+                continue;
+            };
+            let Ok(raw) = std::str::from_utf8(text) else {
+                continue;
+            };
+            if let Some(substituted) = substitute(raw, vars) {
+                *text = BytesText::from_escaped(substituted);
+            }
+        }
+    });
+
+    element
+}
+
+/** Substitute placeholders in the attribute values, leaving every other byte of the tag as it is.
+
+Unquoted values containing a placeholder are put in double quotes.*/
+fn substitute_attributes(element: &mut Element, vars: &HashMap<String, String>) {
+    let Ok(content) = std::str::from_utf8(&element.element) else {
+        return;
+    };
+    let name_len = element.element.name().as_ref().len();
+
+    let mut spliced = String::new();
+    let mut written = 0;
+
+    for span in attribute_spans(content.as_bytes(), name_len) {
+        let Some(range) = span.value else {
+            continue;
+        };
+        let Some(value) = substitute(&content[range.clone()], vars) else {
+            continue;
+        };
+
+        spliced.push_str(&content[written..range.start]);
+        if span.quote.is_some() {
+            spliced.push_str(&value);
+        } else {
+            spliced.push('"');
+            spliced.push_str(&value.replace('"', "&quot;"));
+            spliced.push('"');
+        }
+        written = range.end;
+    }
+
+    if written == 0 {
+        return;
+    }
+
+    spliced.push_str(&content[written..]);
+    element.element = BytesStart::from_content(spliced, name_len);
+}
+
+/** Replace the placeholders in escaped text. Returns `None` if nothing was replaced. */
+fn substitute(raw: &str, vars: &HashMap<String, String>) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = raw;
+    let mut replaced = false;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + length;
+
+        result.push_str(&rest[..start]);
+        match vars.get(rest[start + 2..end].trim()) {
+            Some(value) => {
+                result.push_str(&escape(value));
+                replaced = true;
+            }
+            None => result.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+
+    if !replaced {
+        return None;
+    }
+
+    result.push_str(rest);
+    Some(result)
+}
@@ -1,5 +1,8 @@
-use quick_xml::events::Event;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesText, Event};
 use quick_xml::name::QName;
+use std::borrow::Cow;
+use std::ops::Range;
 use std::string::FromUtf8Error;
 
 pub fn qname_to_string(qname: &QName) -> Result<String, FromUtf8Error> {
@@ -10,12 +13,154 @@ pub fn u8_to_string(u8: &[u8]) -> Result<String, FromUtf8Error> {
     String::from_utf8(u8.to_vec())
 }
 
+/** Check if a byte is XML whitespace: space, tab, carriage return or line feed. */
+pub fn is_xml_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+/** Escape double quotes in a raw attribute value, so it can be written in double quotes. */
+pub fn escape_quotes(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value.contains(&b'"') {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = Vec::with_capacity(value.len() + 5);
+    for &byte in value {
+        match byte {
+            b'"' => escaped.extend_from_slice(b"&quot;"),
+            byte => escaped.push(byte),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/** The position of an attribute as it is written in a tag. See [`attribute_spans`]. */
+pub struct AttributeSpan {
+    /** The key. */
+    pub key: Range<usize>,
+    /** The value without quotes, or `None` if there is no `=`. */
+    pub value: Option<Range<usize>>,
+    /** The quote around the value, if any. */
+    pub quote: Option<u8>,
+    /** The end of the attribute, after the value and its closing quote, if any. */
+    pub end: usize,
+}
+
+impl AttributeSpan {
+    /** The whole attribute, from the start of the key to the end of the value. */
+    pub fn range(&self) -> Range<usize> {
+        self.key.start..self.end
+    }
+}
+
+/** Find the attributes of a tag as they are written, starting after the name.
+
+Unlike the quick_xml attribute iterator, this never fails, so malformed attributes like unquoted values,
+keys without a value and duplicates are found as well.*/
+pub fn attribute_spans(tag: &[u8], name_len: usize) -> Vec<AttributeSpan> {
+    let skip_whitespace = |mut pos: usize| {
+        while pos < tag.len() && is_xml_whitespace(tag[pos]) {
+            pos += 1;
+        }
+        pos
+    };
+
+    let mut spans = Vec::new();
+    let mut pos = skip_whitespace(name_len);
+    while pos < tag.len() {
+        let key_start = pos;
+        while pos < tag.len() && !is_xml_whitespace(tag[pos]) && tag[pos] != b'=' {
+            pos += 1;
+        }
+        let key = key_start..pos;
+
+        let equals = skip_whitespace(pos);
+        if equals == tag.len() || tag[equals] != b'=' {
+            spans.push(AttributeSpan {
+                key,
+                value: None,
+                quote: None,
+                end: pos,
+            });
+            pos = equals;
+            continue;
+        }
+
+        let value_start = skip_whitespace(equals + 1);
+        let quote = tag
+            .get(value_start)
+            .copied()
+            .filter(|byte| matches!(byte, b'"' | b'\''));
+        let (value, end) = match quote {
+            Some(quote) => {
+                let start = value_start + 1;
+                match tag[start..].iter().position(|&byte| byte == quote) {
+                    Some(length) => (start..start + length, start + length + 1),
+                    None => (start..tag.len(), tag.len()),
+                }
+            }
+            None => {
+                let mut end = value_start;
+                while end < tag.len() && !is_xml_whitespace(tag[end]) {
+                    end += 1;
+                }
+                (value_start..end, end)
+            }
+        };
+        spans.push(AttributeSpan {
+            key,
+            value: Some(value),
+            quote,
+            end,
+        });
+        pos = skip_whitespace(end);
+    }
+    spans
+}
+
+/** Escape text, reusing an owned string if nothing needs to be escaped. */
+pub fn escaped_text<'a>(content: impl Into<Cow<'a, str>>) -> BytesText<'a> {
+    match content.into() {
+        Cow::Borrowed(content) => BytesText::new(content),
+        Cow::Owned(content) => {
+            let escaped = match escape(&content) {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(escaped) => Some(escaped),
+            };
+            BytesText::from_escaped(escaped.unwrap_or(content))
+        }
+    }
+}
+
+/** A trait for getting the quick_xml events that make up an item.
+
+Useful for writing items with a custom quick_xml `Writer`, e.g. one with different settings or wrapping a compressing stream.
+
+```rust
+# use ilex_xml::*;
+let items = parse("<a><b>text</b></a>")?;
+
+let mut writer = quick_xml::Writer::new_with_indent(Vec::new(), b' ', 1);
+for event in items[0].get_all_events() {
+    writer.write_event(event)?;
+}
+
+assert_eq!(writer.into_inner(), b"<a>\n <b>text</b>\n</a>");
+# Ok::<(), Error>(())
+```*/
 pub trait GetEvents {
-    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event> + '_>;
+    /** Get all events of the item in order, including those of its descendants. */
+    fn get_all_events(&self) -> Box<dyn Iterator<Item = Event<'_>> + '_>;
 }
 
 /** A trait for converting an item to a String without panicing. */
 pub trait ToStringSafe {
-    /** Converts the given item to a `String`, but doesn't panic. */
+    /** Converts the given item to a `String`, but doesn't panic.
+
+    `to_string` doesn't panic either, but replaces invalid UTF-8 with `U+FFFD`.
+    Use this to detect such content instead.
+
+    The error tells which stage failed:
+    - `Error::Io` if writing the XML failed.
+    - `Error::NonDecodable` if the written XML is not valid UTF-8, e.g. because a name or value was parsed from invalid bytes.*/
     fn to_string_safe(&self) -> Result<String, crate::Error>;
 }
@@ -0,0 +1,34 @@
+use std::fmt::Display;
+
+use crate::Error;
+
+/** Data that was skipped by a lossy operation, and why.
+
+Returned by the `*_with_warnings` variants of methods which otherwise ignore errors silently,
+like [`Element::get_text_content_with_warnings`](crate::Element::get_text_content_with_warnings).*/
+#[derive(Debug, Clone)]
+pub enum Warning {
+    /** A text item was skipped because it is not valid UTF-8. */
+    Text(Error),
+    /** An attribute was skipped because it is malformed or not valid UTF-8. */
+    Attribute(Error),
+    /** An item could not be serialized and was skipped. */
+    Item {
+        /** Position of the item in the serialized list. */
+        index: usize,
+        /** Why serializing failed. */
+        error: Error,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Text(err) => write!(f, "skipped text: {err}"),
+            Warning::Attribute(err) => write!(f, "skipped attribute: {err}"),
+            Warning::Item { index, error } => write!(f, "skipped item {index}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Warning {}
@@ -127,7 +127,7 @@ mod tests {
     fn test_add_attribute() {
         let xml = r#"<x></x><a></a><y></y>"#;
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         let Item::Element(element) = &mut items[1] else {
             panic!("Test data is corrupt.");
@@ -144,7 +144,7 @@ mod tests {
     fn test_replace_attribute() {
         let xml = r#"<x></x><a works="no"></a><y></y>"#;
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         let Item::Element(element) = &mut items[1] else {
             panic!("Test data is corrupt.");
@@ -161,7 +161,7 @@ mod tests {
     fn test_set_attributes() {
         let xml = r#"<x></x><a works="no"></a><y></y>"#;
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         let Item::Element(element) = &mut items[1] else {
             panic!("Test data is corrupt.");
@@ -180,7 +180,7 @@ mod tests {
     fn test_has_attribute() {
         let xml = r#"<x></x><a works="no"></a><y></y>"#;
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         let Item::Element(element) = &mut items[1] else {
             panic!("Test data is corrupt.");
@@ -195,7 +195,7 @@ mod tests {
     fn test_add_children() {
         let xml = "<a></a><b><c></c></b>";
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         items.push(Item::new_element("x", false));
 
@@ -222,7 +222,7 @@ mod tests {
     fn test_get_name() {
         let xml = "<a></a>";
 
-        let item = &parse(&xml).unwrap()[0];
+        let item = &parse(xml).unwrap()[0];
 
         let Item::Element(element) = item else {
             panic!("Test data is corrupt.");
@@ -235,7 +235,7 @@ mod tests {
     fn test_set_name() {
         let xml = "<test></test>";
 
-        let mut items = parse(&xml).unwrap();
+        let mut items = parse(xml).unwrap();
 
         let Item::Element(element) = &mut items[0] else {
             panic!("Test data is corrupt.");
@@ -250,7 +250,7 @@ mod tests {
     fn test_get_value() {
         let xml = "hey";
 
-        let items = parse(&xml).unwrap();
+        let items = parse(xml).unwrap();
 
         let Item::Text(text) = &items[0] else {
             panic!("Test data is corrupt.");
@@ -264,7 +264,7 @@ mod tests {
         let xml =
             r#"<a key="1"><b key="1"/><c key="0"/><d key="0"><e key="1">Some Text</e></d></a>"#;
 
-        let items = parse(&xml).unwrap();
+        let items = parse(xml).unwrap();
 
         let Item::Element(a) = &items[0] else {
             panic!("Test data is corrupt.");
@@ -301,4 +301,2560 @@ mod tests {
         assert!(parse(xml_1).is_err());
         assert!(parse(xml_2).is_err());
     }
+
+    #[test]
+    fn test_retain_children() {
+        let xml = "<a> <b/> text <c/> </a>";
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        element.retain_children(|item| !matches!(item, Item::Text(_)));
+
+        assert_eq!(element.to_string(), "<a><b/><c/></a>");
+    }
+
+    #[test]
+    fn test_visit_elements_mut() {
+        let xml = "<a><b><c/></b>text<d/></a>";
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let mut visited = Vec::new();
+
+        element.visit_elements_mut(false, |el| {
+            visited.push(el.get_name().unwrap());
+            el.set_attribute("seen", "yes");
+        });
+
+        assert_eq!(visited, ["b", "c", "d"]);
+        assert_eq!(
+            element.to_string(),
+            r#"<a><b seen="yes"><c seen="yes"/></b>text<d seen="yes"/></a>"#
+        );
+    }
+
+    #[test]
+    fn test_take_child() {
+        let xml = "<a><b/>text</a>";
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.take_child(2).is_none());
+
+        let taken = element.take_child(0).unwrap();
+
+        assert_eq!(taken.to_string(), "<b/>");
+        assert_eq!(element.to_string(), "<a>text</a>");
+    }
+
+    #[test]
+    fn test_bool_attribute() {
+        let xml = r#"<input checked="" disabled="no" a="1" b="true"/>"#;
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.get_bool_attribute("checked").unwrap());
+        assert!(!element.get_bool_attribute("disabled").unwrap());
+        assert!(!element.get_bool_attribute("missing").unwrap());
+
+        element.set_bool_attribute("checked", false);
+        element.set_bool_attribute("disabled", true);
+
+        assert!(!element.has_attribute("checked"));
+        assert!(element.get_bool_attribute("disabled").unwrap());
+    }
+
+    #[test]
+    fn test_remove_attribute() {
+        let xml = r#"<a x="1" y="2" z="3"></a>"#;
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.remove_attribute("y"));
+        assert!(!element.remove_attribute("y"));
+        assert_eq!(element.to_string(), r#"<a x="1" z="3"></a>"#);
+    }
+
+    #[test]
+    fn test_pi_pseudo_attributes() {
+        let xml = r#"<?xml-stylesheet type="text/xsl" href="x.xsl"?><?php echo 1; ?>"#;
+
+        let items = parse(xml).unwrap();
+
+        let Item::PI(stylesheet) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        let Item::PI(php) = &items[1] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(stylesheet.get_target().unwrap().unwrap(), "xml-stylesheet");
+        assert_eq!(
+            stylesheet.get_pseudo_attributes().unwrap().unwrap(),
+            PIData::Attributes(vec![
+                (String::from("type"), String::from("text/xsl")),
+                (String::from("href"), String::from("x.xsl")),
+            ])
+        );
+
+        assert_eq!(php.get_target().unwrap().unwrap(), "php");
+        assert_eq!(
+            php.get_pseudo_attributes().unwrap().unwrap(),
+            PIData::Raw(String::from("echo 1; "))
+        );
+
+        assert!(Other::new_text("x").get_target().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_items_to_string_iterator() {
+        let xml = "<a><x>1</x><y>2</y><x>3</x></a>";
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let not_y = element.children.iter().filter(|item| match item {
+            Item::Element(el) => el.get_name().unwrap() != "y",
+            _ => true,
+        });
+
+        assert_eq!(items_to_string(not_y), "<x>1</x><x>3</x>");
+    }
+
+    #[test]
+    fn test_serialized_len() {
+        let others = vec![
+            Other::new_comment("hey"),
+            Other::new_text("<&>"),
+            Other::new_cdata("x"),
+            Other::new_pi("php").unwrap(),
+            Other::new_decl("1.0", None, None).unwrap(),
+        ];
+
+        for other in &others {
+            assert_eq!(other.serialized_len(), other.to_string().len());
+        }
+
+        let mut element = Element::new("a", true);
+        element.set_attribute("b", "x&y");
+
+        assert_eq!(
+            element.get_attribute_serialized_len("b").unwrap(),
+            Some("x&amp;y".len())
+        );
+        assert_eq!(element.get_attribute_serialized_len("c").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let xml = b"<a b=\"\xff\">\xfe</a>";
+
+        let items = parse_bytes(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.get_name().unwrap(), "a");
+        assert!(element.get_attribute("b").is_err());
+
+        let Item::Text(text) = &element.children[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(text.get_value().is_err());
+    }
+
+    #[test]
+    fn test_text_with_entity_is_single_item() {
+        let xml = "<a>fish &amp; chips&#33;</a>";
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.children.len(), 1);
+        assert_eq!(element.get_text_content(), "fish &amp; chips&#33;");
+    }
+
+    #[test]
+    fn test_cursor_replace() {
+        let xml = r#"<a><b><c id="x"/></b><d/></a>"#;
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let cursor = element
+            .find_cursor(|item| match item {
+                Item::Element(el) => el.has_attribute("id"),
+                _ => false,
+            })
+            .unwrap();
+
+        assert_eq!(cursor.index(), 0);
+
+        cursor.replace(vec![Item::new_element("e", true), Item::new_text("t")]);
+
+        assert_eq!(element.to_string(), "<a><b><e/>t</b><d/></a>");
+
+        assert!(element.cursor(2).is_none());
+
+        element.cursor(1).unwrap().replace(Vec::new());
+
+        assert_eq!(element.to_string(), "<a><b><e/>t</b></a>");
+    }
+
+    #[test]
+    fn test_to_string_pretty() {
+        let xml = "<a><b><c>text</c><!--note--></b><d></d>mixed<e/></a>";
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element.to_string_pretty("\t").unwrap(),
+            "<a>\n\t<b>\n\t\t<c>text</c>\n\t\t<!--note-->\n\t</b>\n\t<d></d>mixed<e/>\n</a>"
+        );
+
+        let Item::Element(b) = &element.children[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            b.to_string_pretty(" ").unwrap(),
+            "<b>\n <c>text</c>\n <!--note-->\n</b>"
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let xml = r#"<a x="1" x="2"><b>text</a></c><d>"#;
+
+        let (items, errors) = parse_lenient(xml);
+
+        assert_eq!(
+            items_to_string(&items),
+            r#"<a x="1" x="2"><b>text</b></a><d></d>"#
+        );
+
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(errors[0].error, Error::InvalidAttr(_)));
+        assert!(matches!(
+            &errors[1].error,
+            Error::IllFormed(quick_xml::errors::IllFormedError::MissingEndTag(name)) if name == "b"
+        ));
+        assert!(matches!(
+            &errors[2].error,
+            Error::IllFormed(quick_xml::errors::IllFormedError::UnmatchedEndTag(name)) if name == "c"
+        ));
+        assert_eq!(errors[2].position, 26);
+        assert_eq!(errors[3].position, xml.len() as u64);
+
+        let (items, errors) = parse_lenient("<a><b/></a>");
+
+        assert_eq!(items.len(), 1);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_find_descendants_capturing_predicate() {
+        let xml = r#"<a><b key="1"/><c key="2"/></a>"#;
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(a) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let wanted = String::from("2");
+        let predicate = |item: &Item| match item {
+            Item::Element(el) => el.get_attribute("key").unwrap().as_ref() == Some(&wanted),
+            _ => false,
+        };
+
+        let found: Vec<_> = a.find_descendants(&predicate).collect();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].to_string(), r#"<c key="2"/>"#);
+    }
+
+    #[test]
+    fn test_eq_ignoring_style() {
+        let items = parse(r#"<x a="1"><y/></x><x  a="1"><y></y></x><x a="2"><y/></x>"#).unwrap();
+
+        let [Item::Element(first), Item::Element(second), Item::Element(third)] = &items[..] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_ne!(first, second);
+        assert!(first.eq_ignoring_style(second));
+        assert!(!first.eq_ignoring_style(third));
+    }
+
+    #[test]
+    fn test_to_string_safe_non_decodable() {
+        let items = parse_bytes(b"<a>\xff</a>").unwrap();
+
+        assert!(matches!(
+            items[0].to_string_safe(),
+            Err(Error::NonDecodable(_))
+        ));
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let xml = r#"<a href="/{{page}}"><b>{{title}}</b><c x="{{missing}}"/></a>"#;
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(template) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let vars = HashMap::from([
+            (String::from("page"), String::from("a\"b")),
+            (String::from("title"), String::from("<Home>")),
+        ]);
+
+        let instance = instantiate(template, &vars);
+
+        assert_eq!(
+            instance.to_string(),
+            r#"<a href="/a&quot;b"><b>&lt;Home&gt;</b><c x="{{missing}}"/></a>"#
+        );
+        assert_eq!(template.to_string(), xml);
+
+        let items = parse(r#"<a title='say "hi"' x="{{page}}"/>"#).unwrap();
+        let Item::Element(template) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let instance = instantiate(template, &vars);
+
+        assert_eq!(
+            instance.to_string(),
+            r#"<a title='say "hi"' x="a&quot;b"/>"#
+        );
+    }
+
+    #[test]
+    fn test_instantiate_keeps_malformed_attributes() {
+        let xml = r#"<a lang="{{l}}" checked x='1' y={{l}}>t</a>"#;
+
+        let (items, _) = parse_lenient(xml);
+
+        let Item::Element(template) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let vars = HashMap::from([(String::from("l"), String::from("en"))]);
+
+        assert_eq!(
+            instantiate(template, &vars).to_string(),
+            r#"<a lang="en" checked x='1' y="en">t</a>"#
+        );
+    }
+
+    #[test]
+    fn test_numeric_attributes() {
+        let xml = r#"<rect x="-1.5" y=" 3 " w="7" h="-7" r="big"/>"#;
+
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.get_f64_attribute("x").unwrap(), Some(-1.5));
+        assert_eq!(element.get_i64_attribute("y").unwrap(), Some(3));
+        assert_eq!(element.get_u64_attribute("w").unwrap(), Some(7));
+        assert_eq!(element.get_u64_attribute("missing").unwrap(), None);
+        assert!(matches!(
+            element.get_u64_attribute("h"),
+            Err(AttributeValueError::Parse(_))
+        ));
+        assert!(matches!(
+            element.get_f64_attribute("r"),
+            Err(AttributeValueError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_trimmed_xml_space() {
+        let xml = r#"<a> x <b xml:space="preserve"> y <c> z </c><d xml:space="default"> w </d></b> v </a>"#;
+
+        let items = parse_trimmed(xml).unwrap();
+
+        assert_eq!(
+            items_to_string(&items),
+            r#"<a>x<b xml:space="preserve"> y <c> z </c><d xml:space="default">w</d></b>v</a>"#
+        );
+    }
+
+    #[test]
+    fn test_attribute_count() {
+        let items = parse(r#"<a x="1" y="2" x="3"></a><b></b>"#).unwrap();
+
+        let [Item::Element(a), Item::Element(b)] = &items[..] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(a.attribute_count(), 2);
+        assert_eq!(b.attribute_count(), 0);
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let valid = "<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n<!-- prolog -->\n<html><p/></html>\n<?epilog pi?>\n";
+
+        let root = parse_document(valid).unwrap();
+
+        assert_eq!(root.get_name().unwrap(), "html");
+        assert_eq!(root.children.len(), 1);
+
+        assert!(matches!(
+            parse_document("<!-- only a comment -->"),
+            Err(DocumentError::MissingRoot)
+        ));
+        assert!(matches!(
+            parse_document("<a/><b/>"),
+            Err(DocumentError::MultipleRoots)
+        ));
+        assert!(matches!(
+            parse_document("<a/>text"),
+            Err(DocumentError::MisplacedItem)
+        ));
+        assert!(matches!(
+            parse_document("<a/><!DOCTYPE a>"),
+            Err(DocumentError::MisplacedItem)
+        ));
+        assert!(matches!(parse_document("<a>"), Err(DocumentError::Xml(_))));
+    }
+
+    #[test]
+    fn test_inline_text_limit() {
+        let xml =
+            "<ul><li>short</li><li>this one is long</li><li>mixed <b>content</b> here</li></ul>";
+
+        let items = parse(xml).unwrap();
+
+        let options = SerializeOptions {
+            indent: Some(String::from("  ")),
+            inline_text_limit: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            items[0].to_string_with(&options).unwrap(),
+            "<ul>\n  <li>short</li>\n  <li>\n    this one is long\n  </li>\n  <li>mixed <b>content</b> here</li>\n</ul>"
+        );
+    }
+
+    #[test]
+    fn test_filter_stream() {
+        let input = "<!--feed--><item date=\"2024\"><a>x</a></item>\n<item date=\"2020\"/>\n<item date=\"2025\">y</item>";
+
+        let mut output = Vec::new();
+
+        filter_stream(input.as_bytes(), &mut output, |item| match item {
+            Item::Element(element) => element.get_u64_attribute("date").unwrap().unwrap() > 2022,
+            _ => true,
+        })
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<!--feed--><item date=\"2024\"><a>x</a></item>\n\n<item date=\"2025\">y</item>"
+        );
+
+        let mut output = Vec::new();
+
+        assert!(filter_stream("<a><b></b>".as_bytes(), &mut output, |_| true).is_err());
+    }
+
+    #[test]
+    fn test_name_equals() {
+        let items = parse("<svg:rect/>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.name_equals("svg:rect"));
+        assert!(!element.name_equals("rect"));
+        assert!(!element.name_equals("svg:rec"));
+    }
+
+    #[test]
+    fn test_format_path() {
+        let items = parse("<root><a/><b><!--x-->text<c/></b><a><c/></a></root>").unwrap();
+
+        let Item::Element(root) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(root.format_path(&[]).unwrap(), "/root");
+        assert_eq!(root.format_path(&[0]).unwrap(), "/root/a[1]");
+        assert_eq!(root.format_path(&[1, 1]).unwrap(), "/root/b/text()");
+        assert_eq!(root.format_path(&[2, 0]).unwrap(), "/root/a[2]/c");
+        assert_eq!(root.format_path(&[3]), None);
+        assert_eq!(root.format_path(&[1, 0, 0]), None);
+
+        let path = root
+            .find_with_path(&|item| matches!(item, Item::Element(el) if el.name_equals("c")))
+            .unwrap();
+
+        assert_eq!(root.format_path(&path).unwrap(), "/root/b/c");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut items = parse(r#"<a x="1" y="2"><b/>text</a>"#).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        element.clear_attributes();
+        assert_eq!(element.attribute_count(), 0);
+        assert_eq!(element.to_string(), "<a><b/>text</a>");
+
+        element.clear_children();
+        assert!(element.children.is_empty());
+        assert_eq!(element.to_string(), "<a></a>");
+    }
+
+    #[test]
+    fn test_set_attribute_keeps_order() {
+        let svg = read_to_string("test_data/attribute_order.svg").unwrap();
+        let mut items = parse(&svg).unwrap();
+
+        let Some(Item::Element(root)) = items
+            .iter_mut()
+            .find(|item| matches!(item, Item::Element(_)))
+        else {
+            panic!("Test data is corrupt.");
+        };
+
+        let mut before = Vec::new();
+        root.visit_elements_mut(true, |element| {
+            before.push(element.get_all_attributes().collect::<Vec<_>>())
+        });
+
+        root.visit_elements_mut(true, |element| {
+            if element.get_attribute("id").unwrap().as_deref() == Some("rect1") {
+                element.set_attribute("width", "60 & more");
+            }
+        });
+
+        let mut after = Vec::new();
+        root.visit_elements_mut(true, |element| {
+            after.push(element.get_all_attributes().collect::<Vec<_>>())
+        });
+
+        let changed: Vec<_> = before
+            .iter()
+            .flatten()
+            .zip(after.iter().flatten())
+            .filter(|(old, new)| old != new)
+            .collect();
+
+        assert_eq!(before.concat().len(), after.concat().len());
+        assert_eq!(
+            changed,
+            [(
+                &(String::from("width"), String::from("50.8")),
+                &(String::from("width"), String::from("60 &amp; more"))
+            )]
+        );
+
+        let output = items_to_string(&items);
+        assert!(output.contains(
+            r#"<rect style="fill:#ff0000;stroke:none;stroke-width:0.264583" id="rect1" width="60 &amp; more" height="31.75" x="25.4" y="38.1" ry="4.2"/>"#
+        ));
+        assert_eq!(
+            output.replace(char::is_whitespace, "").len(),
+            svg.replace(char::is_whitespace, "").len() + "60&amp;more".len() - "50.8".len()
+        );
+    }
+
+    #[test]
+    fn test_set_attribute_single_quoted_neighbour() {
+        let mut items = parse(r#"<a title='say "hi"' x="1"/>"#).unwrap();
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        element.set_attribute("x", "2");
+
+        let xml = element.to_string();
+        let reparsed = parse(&xml).unwrap();
+        let Item::Element(reparsed) = &reparsed[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(
+            reparsed.get_attribute_cow("title").unwrap().unwrap(),
+            "say \"hi\""
+        );
+        assert_eq!(reparsed.get_attribute("x").unwrap().unwrap(), "2");
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let xml = "<a x=\"1\"><!--c-->text &amp; more<b/></a>";
+
+        let items = parse_reader(std::io::BufReader::new(xml.as_bytes())).unwrap();
+        assert_eq!(items_to_string(&items), xml);
+
+        let mut input = xml.as_bytes();
+        let reader: &mut dyn std::io::BufRead = &mut input;
+        let items = parse_dyn_reader(reader).unwrap();
+        assert_eq!(items, parse(xml).unwrap());
+
+        assert!(parse_reader("<a><b></a>".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_selector() {
+        let items =
+            parse(r#"<svg:rect id="r1" class="a  b" fill="Tom &amp; Jerry" hidden=""/>"#).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let matches = |selector: &str| element.matches(&selector.parse().unwrap());
+
+        assert!(matches("*"));
+        assert!(matches("svg:rect"));
+        assert!(matches("#r1"));
+        assert!(matches(".b.a"));
+        assert!(matches("[hidden]"));
+        assert!(matches(r#"svg:rect#r1[fill="Tom & Jerry"]"#));
+        assert!(matches("[id=r1]"));
+
+        assert!(!matches("rect"));
+        assert!(!matches("#r2"));
+        assert!(!matches(".c"));
+        assert!(!matches("[x]"));
+        assert!(!matches("[fill='Tom']"));
+
+        assert_eq!("".parse::<Selector>(), Err(SelectorError { position: 0 }));
+        assert_eq!(
+            "a b".parse::<Selector>(),
+            Err(SelectorError { position: 1 })
+        );
+        assert_eq!(
+            "[x=".parse::<Selector>(),
+            Err(SelectorError { position: 3 })
+        );
+        assert_eq!("[x".parse::<Selector>(), Err(SelectorError { position: 2 }));
+    }
+
+    #[test]
+    fn test_get_elements_at_depth() {
+        let items = parse("<a>1<b>2<c>3</c><!--2--></b><d>2</d></a>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let depth = |depth| NonZero::new(depth).unwrap();
+
+        let texts: Vec<String> = element
+            .get_items_at_depth(depth(2))
+            .filter_map(|item| match item {
+                Item::Text(text) => Some(text.get_value().unwrap()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["2", "2"]);
+        assert_eq!(element.get_items_at_depth(depth(2)).count(), 4);
+
+        let names: Vec<String> = element
+            .get_elements_at_depth(depth(2))
+            .map(|el| el.get_name().unwrap())
+            .collect();
+        assert_eq!(names, ["c"]);
+        assert_eq!(element.get_elements_at_depth(depth(3)).count(), 0);
+    }
+
+    #[test]
+    fn test_owned_constructors() {
+        fn build(names: &[&str]) -> Item<'static> {
+            let mut list = Element::new(String::from("list"), false);
+            for name in names {
+                let mut entry = Element::new(format!("{name}-entry"), false);
+                entry.children.push(Item::new_text(format!("<{name}>")));
+                entry
+                    .children
+                    .push(Item::new_comment(String::from("generated")));
+                list.children.push(Item::Element(entry));
+            }
+            list.children.push(Item::new_cdata(String::from("a < b")));
+            Item::Element(list)
+        }
+
+        assert_eq!(
+            build(&["a", "b"]).to_string(),
+            "<list><a-entry>&lt;a&gt;<!--generated--></a-entry><b-entry>&lt;b&gt;<!--generated--></b-entry><![CDATA[a < b]]></list>"
+        );
+    }
+
+    #[test]
+    fn test_indented() {
+        let items = parse("<a><b><c/></b></a>").unwrap();
+
+        let options = SerializeOptions::indented(b' ', 3).unwrap();
+        assert_eq!(options, SerializeOptions::pretty("   "));
+        assert_eq!(
+            items[0].to_string_with(&options).unwrap(),
+            "<a>\n   <b>\n      <c/>\n   </b>\n</a>"
+        );
+
+        let options = SerializeOptions::indented(b'\t', 0).unwrap();
+        assert_eq!(
+            items[0].to_string_with(&options).unwrap(),
+            "<a>\n<b>\n<c/>\n</b>\n</a>"
+        );
+
+        assert_eq!(SerializeOptions::indented(b'x', 1), None);
+        assert_eq!(SerializeOptions::indented(0xA0, 1), None);
+    }
+
+    #[test]
+    fn test_text_matches() {
+        let items =
+            parse("<a>Hello<b>wor<!--x-->ld</b><c>WORLD &lt;3</c><![CDATA[world]]></a>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.text_matches("ello", false));
+        assert!(!element.text_matches("world", false));
+        assert!(element.text_matches("world", true));
+        assert!(element.text_matches("<3", false));
+        assert!(!element.text_matches("Helloworld", true));
+
+        let found = element.find_text_nodes_containing("wor", true);
+        let values: Vec<String> = found.iter().map(|text| text.get_value().unwrap()).collect();
+        assert_eq!(values, ["wor", "WORLD &lt;3"]);
+
+        assert!(element.find_text_nodes_containing("xyz", true).is_empty());
+    }
+
+    #[test]
+    fn test_depth_of() {
+        let items = parse("<a>text<b><c id=\"1\"/></b><c id=\"2\"/></a>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.depth_of(|el| el.name_equals("a")), Some(0));
+        assert_eq!(element.depth_of(|el| el.name_equals("b")), Some(1));
+        assert_eq!(element.depth_of(|el| el.name_equals("c")), Some(2));
+        assert_eq!(
+            element.depth_of(|el| el.get_attribute("id").unwrap().as_deref() == Some("2")),
+            Some(1)
+        );
+        assert_eq!(element.depth_of(|_| false), None);
+    }
+
+    #[test]
+    fn test_document_conversions() {
+        let xml = "<!--c--><root a=\"1\">text</root>\n";
+
+        let borrowed = Document::try_from(xml).unwrap();
+        let owned: Document<'static> = Document::try_from(String::from(xml)).unwrap();
+        let parsed: Document<'static> = xml.parse().unwrap();
+
+        assert_eq!(borrowed, owned);
+        assert_eq!(owned, parsed);
+        assert_eq!(parsed.items().len(), 3);
+        assert_eq!(parsed.root().get_text_content(), "text");
+        assert_eq!(parsed.to_string(), xml);
+
+        assert!(matches!(
+            Document::try_from("text"),
+            Err(DocumentError::MisplacedItem)
+        ));
+        assert!(matches!(
+            Document::try_from(String::new()),
+            Err(DocumentError::MissingRoot)
+        ));
+        assert!(matches!(
+            "<a>".parse::<Document>(),
+            Err(DocumentError::Xml(_))
+        ));
+    }
+
+    #[test]
+    fn test_auto_self_close() {
+        let mut items = parse("<a><b></b><c/><d>text</d></a>").unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        element.children.push(Item::new_element("e", false));
+        element.auto_self_close();
+
+        assert_eq!(element.to_string(), "<a><b/><c/><d>text</d><e/></a>");
+        assert!(!element.self_closing);
+
+        element.clear_children();
+        element.auto_self_close();
+
+        assert_eq!(element.to_string(), "<a/>");
+    }
+
+    #[test]
+    fn test_cdata_sections() {
+        for content in ["a]]>b", "]]>]]>", "plain", "", "]]]>>"] {
+            let mut element = Element::new("a", false);
+            element.children = Item::new_cdata_sections(content);
+
+            let xml = element.to_string();
+            let items = parse(&xml).unwrap();
+
+            let Item::Element(element) = &items[0] else {
+                panic!("Output is not an element: {xml}");
+            };
+
+            let parsed: String = element
+                .children
+                .iter()
+                .map(|item| match item {
+                    Item::CData(cdata) => cdata.get_value().unwrap(),
+                    _ => panic!("Unexpected item in {xml}"),
+                })
+                .collect();
+
+            assert_eq!(parsed, content);
+        }
+    }
+
+    #[test]
+    fn test_parse_lenient_repair_attributes() {
+        let xml = r#"<a href=/x?a=1 class='q"uote' hidden><b x=1 x=2>text</b></a>"#;
+
+        let (items, errors) = parse_lenient(xml);
+        assert_eq!(items_to_string(&items), xml);
+        assert!(!errors.is_empty());
+
+        let options = LenientOptions {
+            repair_attributes: true,
+            ..Default::default()
+        };
+        let (items, warnings) = parse_lenient_with(xml, &options);
+
+        assert_eq!(
+            items_to_string(&items),
+            r#"<a href="/x?a=1" class="q&quot;uote" hidden=""><b x="1">text</b></a>"#
+        );
+        assert_eq!(warnings.len(), errors.len());
+        assert!(warnings
+            .iter()
+            .all(|warning| matches!(warning.error, Error::InvalidAttr(_))));
+
+        let Item::Element(a) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(
+            a.get_attribute_normalized("class").unwrap().unwrap(),
+            "q\"uote"
+        );
+
+        let (items, warnings) = parse_lenient_with(r#"<a x="1"/>"#, &options);
+        assert_eq!(items_to_string(&items), r#"<a x="1"/>"#);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_into_descendants() {
+        let xml = "<root><a><b>1<c/></b>2</a><!--3--><d/></root>";
+
+        let Item::Element(root) = parse(xml).unwrap().remove(0) else {
+            panic!("Test data is corrupt.");
+        };
+
+        let descendants: Vec<String> = root
+            .into_descendants()
+            .map(|item| item.to_string())
+            .collect();
+
+        assert_eq!(
+            descendants,
+            ["<a></a>", "<b></b>", "1", "<c/>", "2", "<!--3-->", "<d/>"]
+        );
+
+        let empty = Element::new("x", true);
+        assert_eq!(empty.into_descendants().count(), 0);
+    }
+
+    #[test]
+    fn test_set_attribute_display() {
+        struct Color(u8, u8, u8);
+
+        impl std::fmt::Display for Color {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+            }
+        }
+
+        let mut element = Element::new("rect", true);
+        element.set_attribute_display("width", -3i32);
+        element.set_attribute_display("fill", Color(255, 0, 16));
+        element.set_attribute_display("label", "a & b");
+        element.set_attribute_display("width", 7u8);
+
+        assert_eq!(
+            element.to_string(),
+            r##"<rect width="7" fill="#ff0010" label="a &amp; b"/>"##
+        );
+    }
+
+    #[test]
+    fn test_get_all_events() {
+        let xml = "<?xml version=\"1.0\"?><root a=\"1\"><!--c--><b/>text</root>";
+
+        let document = Document::try_from(xml).unwrap();
+
+        let mut writer = quick_xml::Writer::new(Vec::new());
+        for event in document.get_all_events() {
+            writer.write_event(event).unwrap();
+        }
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), xml);
+
+        let kinds: Vec<&str> = document
+            .root()
+            .get_all_events()
+            .map(|event| match event {
+                quick_xml::events::Event::Start(_) => "start",
+                quick_xml::events::Event::End(_) => "end",
+                quick_xml::events::Event::Empty(_) => "empty",
+                quick_xml::events::Event::Comment(_) => "comment",
+                quick_xml::events::Event::Text(_) => "text",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["start", "comment", "empty", "text", "end"]);
+    }
+
+    #[test]
+    fn test_parse_multi() {
+        let xml = "<?xml version=\"1.0\"?>\n<a/>\n<?xml version=\"1.0\"?><!--b--><b>x</b>";
+
+        let documents = parse_multi(xml).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            items_to_string(&documents[0]),
+            "<?xml version=\"1.0\"?>\n<a/>\n"
+        );
+        assert_eq!(
+            items_to_string(&documents[1]),
+            "<?xml version=\"1.0\"?><!--b--><b>x</b>"
+        );
+
+        let documents = parse_multi("\n  <?xml version=\"1.0\"?><a/>").unwrap();
+        assert_eq!(documents.len(), 1);
+
+        let documents = parse_multi("<x/><?xml version=\"1.0\"?><a/>").unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(items_to_string(&documents[0]), "<x/>");
+
+        assert!(parse_multi("").unwrap().is_empty());
+        assert!(parse_multi("<a>").is_err());
+    }
+
+    #[test]
+    fn test_items_to_bytes() {
+        let xml = "<?xml version=\"1.0\"?><a x=\"&amp;\">text<!--c--></a>";
+        let items = parse(xml).unwrap();
+
+        assert_eq!(items_to_bytes(&items), xml.as_bytes());
+        assert_eq!(
+            items_to_bytes(&items[1..]),
+            items_to_string(&items[1..]).as_bytes()
+        );
+
+        let items = parse_bytes(b"<a>\xff</a>").unwrap();
+        assert_eq!(items_to_bytes(&items), b"<a>\xff</a>");
+        assert_eq!(items_to_string(&items), "");
+    }
+
+    #[test]
+    fn test_declaration_helpers() {
+        let mut items = parse("<!--c--><a/>").unwrap();
+
+        ensure_declaration(&mut items);
+        assert_eq!(
+            items_to_string(&items),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><!--c--><a/>"
+        );
+
+        let mut items = parse("<?xml version=\"1.1\"?><a/>").unwrap();
+        ensure_declaration(&mut items);
+        assert_eq!(items_to_string(&items), "<?xml version=\"1.1\"?><a/>");
+
+        strip_declaration(&mut items);
+        assert_eq!(items_to_string(&items), "<a/>");
+
+        let mut items = Vec::new();
+        strip_declaration(&mut items);
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_get_character_data() {
+        let xml = "<a>1<![CDATA[<2>]]><b>3<!--x--><![CDATA[4]]></b>&amp;5</a>";
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.get_text_content(), "13&amp;5");
+        assert_eq!(element.get_character_data(), "1<2>34&5");
+    }
+
+    #[test]
+    fn test_group_children_by_attribute() {
+        let xml = r#"<r><a k="x" n="1"/>text<a k="y"/><b k="x" n="2"/><c/></r>"#;
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let groups = element.group_children_by_attribute("k", None);
+        assert_eq!(groups.len(), 2);
+        let numbers: Vec<String> = groups["x"]
+            .iter()
+            .map(|el| el.get_attribute("n").unwrap().unwrap())
+            .collect();
+        assert_eq!(numbers, ["1", "2"]);
+        assert_eq!(groups["y"][0].to_string(), r#"<a k="y"/>"#);
+
+        let groups = element.group_children_by_attribute("n", Some(""));
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[""].len(), 2);
+    }
+
+    #[test]
+    fn test_was_self_closing() {
+        for parse_fn in [parse, parse_trimmed] {
+            let items = parse_fn("<r><a/><b></b><c>x</c><d  /></r>").unwrap();
+
+            let Item::Element(root) = &items[0] else {
+                panic!("Test data is corrupt.");
+            };
+
+            let forms: Vec<bool> = root
+                .children
+                .iter()
+                .map(|item| match item {
+                    Item::Element(el) => el.was_self_closing(),
+                    _ => panic!("Test data is corrupt."),
+                })
+                .collect();
+
+            assert_eq!(forms, [true, false, false, true]);
+            assert!(!root.was_self_closing());
+        }
+
+        let (items, _) = parse_lenient("<a/><b></b><c>");
+        let forms: Vec<bool> = items
+            .iter()
+            .map(|item| match item {
+                Item::Element(el) => el.was_self_closing(),
+                _ => panic!("Test data is corrupt."),
+            })
+            .collect();
+        assert_eq!(forms, [true, false, false]);
+    }
+
+    #[test]
+    fn test_bytes_start_bridge() {
+        use quick_xml::events::{BytesStart, Event};
+        use quick_xml::Reader;
+
+        let mut reader = Reader::from_str(r#"<a x="1 &amp; 2"><b/></a>"#);
+        let Event::Start(start) = reader.read_event().unwrap() else {
+            panic!("Test data is corrupt.");
+        };
+
+        let mut element = Element::from_bytes_start(start, false);
+        element.children.push(Item::new_text("text"));
+
+        assert_eq!(element.get_attribute("x").unwrap().unwrap(), "1 &amp; 2");
+        assert_eq!(element.to_string(), r#"<a x="1 &amp; 2">text</a>"#);
+
+        let start: BytesStart = element.into_bytes_start();
+        assert_eq!(start.name().as_ref(), b"a");
+        assert_eq!(start.attributes().count(), 1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_visit_elements() {
+        let xml = read_to_string("test_data/small_inkscape.svg").unwrap();
+        let items = parse(&xml).unwrap();
+
+        let Some(Item::Element(root)) = items.iter().find(|item| matches!(item, Item::Element(_)))
+        else {
+            panic!("Test data is corrupt.");
+        };
+
+        let names = std::sync::Mutex::new(Vec::new());
+        root.par_visit_elements(|el| names.lock().unwrap().push(el.get_name().unwrap()));
+        let mut names = names.into_inner().unwrap();
+
+        let mut expected = vec![root.get_name().unwrap()];
+        root.find_descendants(&|item| matches!(item, Item::Element(_)))
+            .for_each(|item| {
+                let Item::Element(el) = item else {
+                    unreachable!();
+                };
+                expected.push(el.get_name().unwrap());
+            });
+
+        names.sort();
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_attribute_entries() {
+        let items = parse_bytes(b"<a k=\"v\" bin=\"\xff\" x=\"1\" x=\"2\"/>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let entries: Vec<(&[u8], Vec<u8>)> = element
+            .attribute_entries()
+            .map(|(key, value)| (key, value.into_owned()))
+            .collect();
+
+        assert_eq!(
+            entries,
+            [
+                (b"k".as_slice(), b"v".to_vec()),
+                (b"bin".as_slice(), b"\xff".to_vec()),
+                (b"x".as_slice(), b"1".to_vec()),
+            ]
+        );
+
+        assert_eq!(element.get_all_attributes().count(), 2);
+        assert_eq!(element.attribute_count(), 3);
+    }
+
+    #[test]
+    fn test_consistent_error_type() {
+        fn describe(element: &Element) -> Result<String, Error> {
+            let name = element.get_name()?;
+            let id = element.get_attribute("id")?.unwrap_or_default();
+            let text = String::from_utf8(element.get_text_content().into_bytes())?;
+            Ok(format!("{name}#{id}: {text}"))
+        }
+
+        let items = parse_bytes(b"<a id=\"x\">t</a><\xff/>").unwrap();
+
+        let [Item::Element(a), Item::Element(invalid)] = &items[..] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(describe(a).unwrap(), "a#x: t");
+        assert!(matches!(describe(invalid), Err(Error::NonDecodable(_))));
+    }
+
+    #[test]
+    fn test_pretty_print_top_level_items() {
+        let xml = "<?xml version=\"1.0\"?><!DOCTYPE r><!--top--><r><!--own line--><a/><p>text<!--inline-->more</p><p><!--inline-->text</p></r><!--end-->";
+        let items = parse(xml).unwrap();
+
+        assert_eq!(
+            items_to_string_with(&items, &SerializeOptions::pretty("  ")).unwrap(),
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE r>\n<!--top-->\n<r>\n  <!--own line-->\n  <a/>\n  <p>text<!--inline-->more</p>\n  <p><!--inline-->text</p>\n</r>\n<!--end-->"
+        );
+
+        let options = SerializeOptions {
+            top_level_blank_lines: true,
+            ..SerializeOptions::pretty("\t")
+        };
+        assert_eq!(
+            items_to_string_with(&items[2..], &options).unwrap(),
+            "<!--top-->\n\n<r>\n\t<!--own line-->\n\t<a/>\n\t<p>text<!--inline-->more</p>\n\t<p><!--inline-->text</p>\n</r>\n\n<!--end-->"
+        );
+
+        assert_eq!(
+            items_to_string_with(&items, &SerializeOptions::default()).unwrap(),
+            xml
+        );
+    }
+
+    #[test]
+    fn test_children_between() {
+        let items = parse("<r><a/>1<b/>2<c/>3<b/></r>").unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let named = |name: &'static str| move |item: &Item| matches!(item, Item::Element(el) if el.name_equals(name));
+
+        assert_eq!(
+            items_to_string(element.children_between(named("a"), named("b"))),
+            "1"
+        );
+        assert_eq!(
+            items_to_string(element.children_between(named("a"), named("c"))),
+            "1<b/>2"
+        );
+        assert_eq!(
+            items_to_string(element.children_between(named("b"), named("b"))),
+            "2<c/>3"
+        );
+        assert!(element.children_between(named("a"), named("a")).is_empty());
+        assert!(element.children_between(named("c"), named("a")).is_empty());
+        assert!(element.children_between(named("x"), named("b")).is_empty());
+        assert!(element.children_between(named("a"), named("x")).is_empty());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let xml = "<a>line 1\r\nline 2\nline 3\rline 4<!--x\r\ny--><b c=\"&#13;&#10;\"/></a>";
+        let items = parse(xml).unwrap();
+
+        assert_eq!(
+            items_to_string_with(&items, &SerializeOptions::default()).unwrap(),
+            xml
+        );
+
+        let options = SerializeOptions {
+            line_ending: LineEnding::Lf,
+            ..Default::default()
+        };
+        assert_eq!(
+            items_to_string_with(&items, &options).unwrap(),
+            "<a>line 1\nline 2\nline 3\nline 4<!--x\ny--><b c=\"&#13;&#10;\"/></a>"
+        );
+
+        let options = SerializeOptions {
+            line_ending: LineEnding::CrLf,
+            ..SerializeOptions::pretty(" ")
+        };
+        let items = parse("<a>\r\n<b>1\n2</b></a>").unwrap();
+        assert_eq!(
+            items_to_string_with(&items, &options).unwrap(),
+            "<a>\r\n<b>1\r\n2</b>\r\n</a>"
+        );
+    }
+
+    #[test]
+    fn test_get_attribute_ci() {
+        let items = parse(r#"<a ID="1" id="2" Data-X="&amp;"/>"#).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.get_attribute_ci("id").unwrap().unwrap(), "1");
+        assert_eq!(
+            element.get_attribute_ci("data-x").unwrap().unwrap(),
+            "&amp;"
+        );
+        assert_eq!(element.get_attribute_ci("missing").unwrap(), None);
+        assert_eq!(element.get_attribute("data-x").unwrap(), None);
+
+        let items = parse(r#"<a x="1" x="2"/>"#).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert!(element.get_attribute_ci("y").is_err());
+    }
+
+    #[test]
+    fn test_validate_tree() {
+        let mut schema = Schema::default();
+        schema.elements.insert(
+            String::from("list"),
+            ElementSchema {
+                allowed_children: Some(vec![String::from("item")]),
+                required_attributes: vec![String::from("name")],
+            },
+        );
+        schema.elements.insert(
+            String::from("item"),
+            ElementSchema {
+                allowed_children: Some(Vec::new()),
+                required_attributes: vec![String::from("id")],
+            },
+        );
+
+        let root = parse_document(r#"<list name="x"><item id="1">text</item></list>"#).unwrap();
+        assert_eq!(validate_tree(&root, &schema), Ok(()));
+
+        let root =
+            parse_document(r#"<list><item id="1"><b/></item><item/><free><any/></free></list>"#)
+                .unwrap();
+        let errors: Vec<String> = validate_tree(&root, &schema)
+            .unwrap_err()
+            .iter()
+            .map(|error| error.to_string())
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                "/list: missing attribute name",
+                "/list/item[1]/b: unexpected child element b",
+                "/list/item[2]: missing attribute id",
+                "/list/free: unexpected child element free",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_writer() {
+        let mut writer = StreamWriter::new(Vec::new());
+
+        writer.start_element("root", &[("a", "\"1\"")]).unwrap();
+        writer.item(&parse("<!--c--><x>y</x>").unwrap()[1]).unwrap();
+        writer.start_element("inner", &[]).unwrap();
+        assert_eq!(writer.depth(), 2);
+        writer.text("a & b").unwrap();
+        writer.end_element().unwrap();
+        writer.end_element().unwrap();
+        assert_eq!(writer.depth(), 0);
+
+        assert!(matches!(
+            writer.end_element(),
+            Err(Error::IllFormed(
+                quick_xml::errors::IllFormedError::UnmatchedEndTag(_)
+            ))
+        ));
+
+        assert_eq!(
+            String::from_utf8(writer.finish().unwrap()).unwrap(),
+            r#"<root a="&quot;1&quot;"><x>y</x><inner>a &amp; b</inner></root>"#
+        );
+
+        let mut writer = StreamWriter::new(Vec::new());
+        writer.start_element("a", &[]).unwrap();
+        writer.start_element("b", &[]).unwrap();
+        assert!(matches!(
+            writer.finish(),
+            Err(Error::IllFormed(quick_xml::errors::IllFormedError::MissingEndTag(name))) if name == "b"
+        ));
+    }
+
+    #[test]
+    fn test_remove_descendants() {
+        let xml = "<a><x><x/><b><x/></b></x><b><x>t</x><c><x/><x/></c></b><!--x--></a>";
+        let items = parse(xml).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let is_x = |item: &Item| matches!(item, Item::Element(el) if el.name_equals("x"));
+
+        let mut copy = element.clone();
+        assert_eq!(copy.remove_descendants(is_x), 4);
+        assert_eq!(copy.to_string(), "<a><b><c></c></b><!--x--></a>");
+        assert_eq!(copy.remove_descendants(is_x), 0);
+
+        let mut copy = element.clone();
+        assert_eq!(
+            copy.remove_descendants(|item| matches!(item, Item::Comment(_) | Item::Text(_))),
+            2
+        );
+    }
+
+    #[test]
+    fn test_attribute_references_round_trip() {
+        let xml = r#"<a title="line&#xA;break&#10;&#x9;tab &amp; &quot;q&quot; &lt;" other="1"/>"#;
+
+        let mut items = parse(xml).unwrap();
+        assert_eq!(items_to_string(&items), xml);
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element.get_attribute("title").unwrap().unwrap(),
+            "line&#xA;break&#10;&#x9;tab &amp; &quot;q&quot; &lt;"
+        );
+        assert_eq!(
+            element.get_attribute_cow("title").unwrap().unwrap(),
+            "line\nbreak\n\ttab & \"q\" <"
+        );
+
+        element.set_attribute("other", "2");
+        element.remove_attribute("missing");
+        element.set_bool_attribute("flag", true);
+
+        assert_eq!(
+            element.to_string(),
+            r#"<a title="line&#xA;break&#10;&#x9;tab &amp; &quot;q&quot; &lt;" other="2" flag="true"/>"#
+        );
+    }
+
+    #[test]
+    fn test_with_attributes() {
+        let keys = ["z", "a", "m", "b"];
+        let element = Element::with_attributes("e", keys.iter().map(|key| (*key, "v")));
+
+        let order: Vec<&[u8]> = element.attribute_entries().map(|(key, _)| key).collect();
+        assert_eq!(order, [b"z", b"a", b"m", b"b"]);
+
+        assert_eq!(element.to_string(), r#"<e z="v" a="v" m="v" b="v"/>"#);
+    }
+
+    #[test]
+    fn test_get_own_text() {
+        let xml = "<button>Save<icon>floppy</icon> file<!-- hint --><![CDATA[!]]></button>";
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.get_own_text(), "Save file");
+        assert_eq!(element.get_text_content(), "Savefloppy file");
+    }
+
+    #[test]
+    fn test_get_all_attributes_order() {
+        let xml = r#"<rect y="2" x="1" width="10" height="5" id="r" class="box" fill="red"/>"#;
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let keys: Vec<String> = element.get_all_attributes().map(|(key, _)| key).collect();
+
+        assert_eq!(keys, ["y", "x", "width", "height", "id", "class", "fill"]);
+    }
+
+    #[test]
+    fn test_is_whitespace() {
+        let xml = "<a>\n\t<b/>&#32;&#x9;<c/>x<![CDATA[ ]]><!-- --><d/>&amp;</a>";
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let whitespace: Vec<bool> = element
+            .children
+            .iter()
+            .map(|child| child.is_whitespace())
+            .collect();
+
+        assert_eq!(
+            whitespace,
+            [true, false, true, false, false, true, false, false, false]
+        );
+        assert!(
+            !Item::Text(Other::Text(quick_xml::events::BytesText::from_escaped(
+                "&bogus; "
+            )))
+            .is_whitespace()
+        );
+    }
+
+    #[test]
+    fn test_parse_cdata_as_text() {
+        let xml = "<r><s>if (a &lt; b)<![CDATA[ { c && d }]]></s><![CDATA[]]><t><![CDATA[\"x\"]]></t></r>";
+        let options = ParseOptions {
+            cdata_as_text: true,
+            ..Default::default()
+        };
+
+        let items = parse_with(xml, &options).unwrap();
+        let Item::Element(root) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(root
+            .find_with_path(&|item| matches!(item, Item::CData(_)))
+            .is_none());
+
+        assert_eq!(
+            root.to_string(),
+            "<r><s>if (a &lt; b) { c &amp;&amp; d }</s><t>&quot;x&quot;</t></r>"
+        );
+
+        let default = parse_with(xml, &ParseOptions::default()).unwrap();
+        assert_eq!(default, parse(xml).unwrap());
+    }
+
+    #[test]
+    fn test_ensure_child() {
+        let mut items = parse("<a><b x=\"1\"/>text<c/><b x=\"2\"/></a>").unwrap();
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element
+                .ensure_child("b")
+                .get_attribute("x")
+                .unwrap()
+                .unwrap(),
+            "1"
+        );
+        assert_eq!(element.children.len(), 4);
+
+        element
+            .ensure_child("d")
+            .ensure_child("e")
+            .set_attribute("y", "3");
+        element.ensure_child("d").ensure_child("e");
+
+        assert_eq!(
+            element.to_string(),
+            r#"<a><b x="1"/>text<c/><b x="2"/><d><e y="3"/></d></a>"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip_is_byte_exact() {
+        for path in [
+            "test_data/attribute_order.svg",
+            "test_data/small_inkscape.svg",
+            "test_data/tiny_people.xml",
+        ] {
+            let xml = read_to_string(path).unwrap();
+            assert_eq!(items_to_string(&parse(&xml).unwrap()), xml);
+        }
+
+        let xml = "<?xml version = '1.0'  encoding=\"UTF-8\" ?>\r\n<!DOCTYPE svg>\n<svg  a = 'x' b=\"&amp;&#38;\" >\n\t<g/><g ></g><g\n/>text &lt; more<![CDATA[ c ]]><!-- c --><?pi  data ?></svg>\n<!--end-->\n";
+
+        let mut items = parse(xml).unwrap();
+        assert_eq!(items_to_string(&items), xml);
+
+        let Some(Item::Element(svg)) = items.get_mut(4) else {
+            panic!("Test data is corrupt.");
+        };
+        let Some(Item::Element(g)) = svg.children.get_mut(2) else {
+            panic!("Test data is corrupt.");
+        };
+        g.set_attribute("id", "edited");
+
+        assert_eq!(
+            items_to_string(&items),
+            xml.replace("<g ></g>", "<g id=\"edited\"></g>")
+        );
+
+        // the known exceptions, which are normalized
+        let xml = "<!DOCTYPE  svg><svg></svg >";
+        assert_eq!(
+            items_to_string(&parse(xml).unwrap()),
+            "<!DOCTYPE svg><svg></svg>"
+        );
+    }
+
+    #[test]
+    fn test_get_attribute_all() {
+        let (items, errors) = parse_lenient(r#"<a class="x" id="1" class="y &amp; z" class="w"/>"#);
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(!errors.is_empty());
+        assert_eq!(
+            element.get_attribute_all("class").unwrap(),
+            ["x", "y &amp; z", "w"]
+        );
+        assert_eq!(element.get_attribute_all("id").unwrap(), ["1"]);
+        assert_eq!(element.get_attribute("class").unwrap().unwrap(), "x");
+    }
+
+    #[test]
+    fn test_item_ordering() {
+        let xml = r#"<?pi x?><b/><a><c/></a>text<a/><a></a><!--z--><![CDATA[d]]><a x="1"/><!--a--><a><b/></a>"#;
+
+        let mut items = parse(xml).unwrap();
+        items.sort();
+
+        assert_eq!(
+            items_to_string(&items),
+            r#"<a></a><a/><a><b/></a><a><c/></a><a x="1"/><b/><!--a--><!--z-->text<![CDATA[d]]><?pi x?>"#
+        );
+
+        let mut reversed = parse(xml).unwrap();
+        reversed.reverse();
+        reversed.sort();
+        assert_eq!(reversed, items);
+
+        for a in &items {
+            for b in &items {
+                assert_eq!(a == b, a.cmp(b) == std::cmp::Ordering::Equal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_invalid_utf8() {
+        let xml = b"<a x=\"\xFE\">\xFD<!--\xFC--></a>";
+
+        let items = parse_bytes(xml).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(element.to_string_safe().is_err());
+        assert_eq!(
+            element.to_string(),
+            "<a x=\"\u{FFFD}\">\u{FFFD}<!--\u{FFFD}--></a>"
+        );
+        assert_eq!(format!("{}", element.children[1]), "<!--\u{FFFD}-->");
+        assert_eq!(items_to_string(&items), "");
+    }
+
+    #[test]
+    fn test_extract_first() {
+        let mut xml =
+            String::from("<feed><head><meta name=\"a\"/><meta name=\"b\">x<meta/></meta></head>");
+        for i in 0..1000 {
+            xml.push_str(&format!("<entry id=\"{i}\"><meta name=\"c\"/></entry>"));
+        }
+        xml.push_str("<broken></feed>");
+
+        let meta = extract_first(xml.as_bytes(), "meta").unwrap().unwrap();
+        assert_eq!(meta.to_string(), r#"<meta name="a"/>"#);
+
+        let head = extract_first(xml.as_bytes(), "head").unwrap().unwrap();
+        assert_eq!(head.find_children("meta").count(), 2);
+
+        let entry = extract_first(xml.as_bytes(), "entry").unwrap().unwrap();
+        assert_eq!(entry.get_attribute("id").unwrap().unwrap(), "0");
+
+        assert!(extract_first(xml.as_bytes(), "missing").is_err());
+        assert!(extract_first("<a><b/></a>".as_bytes(), "c")
+            .unwrap()
+            .is_none());
+        assert!(extract_first("<a><b>".as_bytes(), "b").is_err());
+    }
+
+    #[test]
+    fn test_set_children() {
+        let mut items = parse("<a><old/>text</a><b/>").unwrap();
+
+        let Item::Element(a) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        a.set_children(vec![Item::new_element("new", true), Item::new_comment("c")]);
+        a.set_children(Vec::new());
+        assert!(!a.self_closing);
+
+        let Item::Element(b) = &mut items[1] else {
+            panic!("Test data is corrupt.");
+        };
+        b.set_children(Vec::new());
+        assert!(b.self_closing);
+        b.set_children(vec![Item::new_element("new", true), Item::new_comment("c")]);
+        assert!(!b.self_closing);
+
+        assert_eq!(items_to_string(&items), "<a></a><b><new/><!--c--></b>");
+    }
+
+    #[test]
+    fn test_parse_lenient_void_elements() {
+        let html =
+            r#"<div><P>one<BR>two</P><hr><input type="text"></input><img src="a.png"/></div>"#;
+
+        let (items, errors) = parse_lenient(html);
+        assert!(!errors.is_empty());
+        assert_ne!(items_to_string(&items), html);
+
+        let (items, warnings) = parse_lenient_with(html, &LenientOptions::html());
+        assert!(warnings.is_empty());
+        assert_eq!(
+            items_to_string(&items),
+            r#"<div><P>one<BR/>two</P><hr/><input type="text"/><img src="a.png"/></div>"#
+        );
+
+        let options = LenientOptions {
+            void_elements: vec![String::from("item")],
+            ..Default::default()
+        };
+        let (items, warnings) = parse_lenient_with("<list><item>a<item>b</list>", &options);
+        assert!(warnings.is_empty());
+        assert_eq!(items_to_string(&items), "<list><item/>a<item/>b</list>");
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let xml = r#"<r>
+            <a x="1"  y="2"><b>text</b><!--c--></a>
+            <a y="2" x="1"><b>text</b><!--c--></a>
+            <a x="1" y="2"><b>text</b>text<!--c--></a>
+            <a x="1" y="2"><b>text</b><![CDATA[c]]></a>
+            <a x="1" y="3"><b>text</b><!--c--></a>
+            <a x="1" y="2"><b/><!--c--></a>
+        </r>"#;
+
+        let Item::Element(root) = &parse_trimmed(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+        let elements: Vec<&Element> = root.find_children("a").collect();
+        let hashes: Vec<u64> = elements
+            .iter()
+            .map(|element| element.content_hash())
+            .collect();
+
+        assert_eq!(hashes[0], hashes[1]);
+        for hash in &hashes[2..] {
+            assert_ne!(hashes[0], *hash);
+        }
+
+        let mut copy = elements[0].clone();
+        copy.self_closing = !copy.self_closing;
+        assert_eq!(copy.content_hash(), hashes[0]);
+    }
+
+    #[test]
+    fn test_take_attribute() {
+        let (mut items, _) = parse_lenient(r#"<a x="1" y="a &amp; b" x="2" z="3"/>"#);
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.take_attribute("missing").unwrap(), None);
+        assert_eq!(
+            element.to_string(),
+            r#"<a x="1" y="a &amp; b" x="2" z="3"/>"#
+        );
+
+        assert_eq!(element.take_attribute("x").unwrap().unwrap(), "1");
+        assert_eq!(element.take_attribute("x").unwrap(), None);
+        assert_eq!(element.to_string(), r#"<a y="a &amp; b" z="3"/>"#);
+
+        assert_eq!(element.take_attribute("y").unwrap().unwrap(), "a &amp; b");
+        assert_eq!(element.to_string(), r#"<a z="3"/>"#);
+    }
+
+    #[test]
+    fn test_walk() {
+        let xml = "<a>x<b><c/>y</b><d></d><!--z--></a>";
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let steps: Vec<String> = element
+            .walk()
+            .map(|event| match event {
+                WalkEvent::Enter(element) => format!("+{}", element.get_name().unwrap()),
+                WalkEvent::Leaf(item) => item.to_string(),
+                WalkEvent::Exit(element) => format!("-{}", element.get_name().unwrap()),
+            })
+            .collect();
+
+        assert_eq!(
+            steps,
+            ["+a", "x", "+b", "+c", "-c", "y", "-b", "+d", "-d", "<!--z-->", "-a"]
+        );
+
+        let mut deep = Element::new("deep", true);
+        for _ in 0..100_000 {
+            let mut parent = Element::new("deep", false);
+            parent.children.push(Item::Element(deep));
+            deep = parent;
+        }
+        assert_eq!(deep.walk().count(), 200_002);
+        // dismantle the tree without recursing, dropping it as a whole would overflow the stack
+        assert_eq!(deep.into_descendants().count(), 100_000);
+    }
+
+    #[test]
+    fn test_parse_skip_comments_and_pis() {
+        let xml =
+            "<?xml version=\"1.0\"?><!--head--><?style a?><r>a<!--x-->b<?pi?><c><!--y--></c></r>";
+
+        let options = ParseOptions {
+            keep_comments: false,
+            ..Default::default()
+        };
+        let items = parse_with(xml, &options).unwrap();
+        assert_eq!(
+            items_to_string(&items),
+            "<?xml version=\"1.0\"?><?style a?><r>ab<?pi?><c></c></r>"
+        );
+
+        let options = ParseOptions {
+            keep_processing_instructions: false,
+            ..Default::default()
+        };
+        let items = parse_with(xml, &options).unwrap();
+        assert_eq!(
+            items_to_string(&items),
+            "<?xml version=\"1.0\"?><!--head--><r>a<!--x-->b<c><!--y--></c></r>"
+        );
+
+        let Item::Element(root) = &items[2] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(root.children.len(), 4);
+
+        assert_eq!(
+            parse_with(xml, &ParseOptions::default()).unwrap(),
+            parse(xml).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nth_child_element() {
+        let xml = "<table>\n  <tr>1</tr>\n  <!-- gap -->\n  <tr>2</tr>\n  <tr>3</tr>\n</table>";
+
+        let mut items = parse(xml).unwrap();
+        let Item::Element(table) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(table.nth_child_element(0).unwrap().get_text_content(), "1");
+        assert_eq!(table.nth_child_element(2).unwrap().get_text_content(), "3");
+        assert!(table.nth_child_element(3).is_none());
+
+        table
+            .nth_child_element_mut(1)
+            .unwrap()
+            .set_attribute("class", "second");
+        assert!(table.children[5]
+            .to_string()
+            .starts_with("<tr class=\"second\">"));
+    }
+
+    #[test]
+    fn test_parse_with_report() {
+        let xml = read_to_string("test_data/attribute_order.svg").unwrap();
+
+        let (items, report) = parse_with_report(&xml).unwrap();
+        assert_eq!(items, parse(&xml).unwrap());
+
+        let mut elements = 0;
+        let mut attributes = 0;
+        let mut depth = 0;
+        let mut max_depth = 0;
+        for item in &items {
+            let Item::Element(root) = item else {
+                continue;
+            };
+            for event in root.walk() {
+                match event {
+                    WalkEvent::Enter(element) => {
+                        elements += 1;
+                        attributes += element.attribute_count();
+                        depth += 1;
+                        max_depth = max_depth.max(depth);
+                    }
+                    WalkEvent::Exit(_) => depth -= 1,
+                    WalkEvent::Leaf(_) => (),
+                }
+            }
+        }
+
+        assert_eq!(report.elements, elements);
+        assert_eq!(report.attributes, attributes);
+        assert_eq!(report.max_depth, max_depth);
+        assert_eq!(report.malformed_attributes, 0);
+
+        let (_, report) = parse_with_report("text<!--c-->").unwrap();
+        assert_eq!(report, ParseReport::default());
+        assert!(parse_with_report("<a>").is_err());
+    }
+
+    #[test]
+    fn test_with_cdata() {
+        let content = "<p>a]]>b</p> & ]]]]>>";
+
+        let element = Element::with_cdata("code", content);
+        let xml = element.to_string();
+
+        let Item::Element(parsed) = &parse(&xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(parsed, &element);
+        assert_eq!(parsed.get_character_data(), content);
+
+        assert_eq!(
+            Element::with_cdata("empty", "").to_string(),
+            "<empty><![CDATA[]]></empty>"
+        );
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_parse_gz_reader() {
+        use std::io::Write;
+
+        let xml = read_to_string("test_data/attribute_order.svg").unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let items = parse_gz_reader(compressed.as_slice()).unwrap();
+        assert_eq!(items, parse(&xml).unwrap());
+
+        assert!(matches!(parse_gz_reader(xml.as_bytes()), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_get_attribute_local() {
+        let xml = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:a="http://www.w3.org/1999/xlink" xmlns:b="http://www.w3.org/1999/xlink">
+            <use a:href="#first"/>
+            <use b:href="#second" href="#plain"/>
+            <use title="none"/>
+        </svg>"##;
+
+        let Item::Element(svg) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let hrefs: Vec<Option<String>> = svg
+            .find_children("use")
+            .map(|element| element.get_attribute_local("href").unwrap())
+            .collect();
+        assert_eq!(
+            hrefs,
+            [
+                Some(String::from("#first")),
+                Some(String::from("#second")),
+                None
+            ]
+        );
+
+        assert_eq!(svg.get_attribute_local("xmlns").unwrap(), None);
+        assert_eq!(svg.get_attribute_local("a").unwrap(), None);
+    }
+
+    #[test]
+    fn test_outer_and_inner_xml() {
+        let xml = r#"<section id="s"><h1>Title</h1>text &amp; more<!--c--><br/></section>"#;
+
+        let Item::Element(section) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(section.outer_xml().unwrap(), xml);
+        assert_eq!(
+            section.inner_xml().unwrap(),
+            "<h1>Title</h1>text &amp; more<!--c--><br/>"
+        );
+
+        let Item::Element(empty) = &parse("<a/>").unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(empty.outer_xml().unwrap(), "<a/>");
+        assert_eq!(empty.inner_xml().unwrap(), "");
+    }
+
+    #[test]
+    fn test_set_inner_xml() {
+        let mut items = parse("<doc><body old=\"1\"><p>old</p></body></doc>").unwrap();
+        let Item::Element(doc) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let fragment = String::from("text &lt; <!--c--><p a='1'>x</p><![CDATA[y]]>");
+        doc.ensure_child("body").set_inner_xml(&fragment).unwrap();
+        drop(fragment);
+
+        let body = doc.ensure_child("body");
+        assert_eq!(
+            body.inner_xml().unwrap(),
+            "text &lt; <!--c--><p a='1'>x</p><![CDATA[y]]>"
+        );
+        assert_eq!(body.children.len(), 4);
+
+        assert!(body.set_inner_xml("<p></q>").is_err());
+        assert!(body.set_inner_xml("</p>").is_err());
+        assert_eq!(body.children.len(), 4);
+
+        body.set_inner_xml("").unwrap();
+        assert_eq!(
+            items_to_string(&items),
+            "<doc><body old=\"1\"></body></doc>"
+        );
+    }
+
+    #[test]
+    fn test_serialize_sort_attributes() {
+        let xml = r##"<svg width="10" xmlns:xlink="http://www.w3.org/1999/xlink" height='5' xmlns="http://www.w3.org/2000/svg"><use y="1" xlink:href="#a" title='say "hi"' x="2"/><g/></svg>"##;
+
+        let items = parse(xml).unwrap();
+        let options = SerializeOptions {
+            sort_attributes: true,
+            ..Default::default()
+        };
+
+        let sorted = items_to_string_with(&items, &options).unwrap();
+        assert_eq!(
+            sorted,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" height="5" width="10"><use title="say &quot;hi&quot;" x="2" xlink:href="#a" y="1"/><g/></svg>"##
+        );
+
+        let reparsed = parse(&sorted).unwrap();
+        assert_eq!(items_to_string_with(&reparsed, &options).unwrap(), sorted);
+
+        let options = SerializeOptions {
+            sort_attributes: true,
+            ..SerializeOptions::pretty(" ")
+        };
+        assert!(items_to_string_with(&items, &options)
+            .unwrap()
+            .contains("\n <use title="));
+        assert_eq!(items_to_string(&items), xml);
+    }
+
+    #[test]
+    fn test_parse_owned() {
+        let xml = read_to_string("test_data/attribute_order.svg").unwrap();
+        let expected = items_to_string(&parse(&xml).unwrap());
+        let expected_trimmed = items_to_string(&parse_trimmed(&xml).unwrap());
+
+        let borrowed = parse_owned(xml.as_str()).unwrap();
+        let cow = parse_owned(std::borrow::Cow::Borrowed(xml.as_str())).unwrap();
+        let trimmed = parse_trimmed_owned(xml.clone()).unwrap();
+        let owned = parse_owned(xml).unwrap();
+
+        assert_eq!(items_to_string(&owned), expected);
+        assert_eq!(borrowed, owned);
+        assert_eq!(cow, owned);
+        assert_eq!(items_to_string(&trimmed), expected_trimmed);
+        assert!(parse_owned(String::from("<a>")).is_err());
+    }
+
+    #[test]
+    fn test_lossy_operations_with_warnings() {
+        let xml = b"<root a=\"\xFE\" b=\"ok\"><p>one</p><p>\xFF</p><p>two</p></root><!--\xFD-->";
+
+        let items = parse_bytes(xml).unwrap();
+        let Item::Element(root) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let (text, warnings) = root.get_text_content_with_warnings();
+        assert_eq!(text, root.get_text_content());
+        assert_eq!(text, "onetwo");
+        assert!(matches!(
+            warnings[..],
+            [Warning::Text(Error::NonDecodable(_))]
+        ));
+
+        let (attributes, warnings) = root.get_all_attributes_with_warnings();
+        assert_eq!(attributes, root.get_all_attributes().collect::<Vec<_>>());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].to_string().starts_with("skipped attribute"));
+
+        let (xml, warnings) = items_to_string_with_warnings(&items);
+        assert_eq!(xml, items_to_string(&items));
+        assert_eq!(xml, "");
+        assert!(matches!(
+            warnings[..],
+            [
+                Warning::Item { index: 0, .. },
+                Warning::Item { index: 1, .. }
+            ]
+        ));
+
+        let (_, warnings) = items_to_string_with_warnings(&parse("<a>b</a>").unwrap());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_renders_same() {
+        let items =
+            parse(r#"<a x="1"><b/></a><a  x="1"><b/></a><a x='1'><b/></a><a x="1"><b></b></a>"#)
+                .unwrap();
+        let elements: Vec<&Element> = items
+            .iter()
+            .map(|item| match item {
+                Item::Element(element) => element,
+                _ => panic!("Test data is corrupt."),
+            })
+            .collect();
+
+        let mut built = Element::with_attributes("a", [("x", "1")]);
+        built.children.push(Item::new_element("b", true));
+
+        assert!(elements[0].renders_same(&built).unwrap());
+        assert!(!elements[0].renders_same(elements[1]).unwrap());
+        assert!(!elements[0].renders_same(elements[2]).unwrap());
+        assert!(!elements[0].renders_same(elements[3]).unwrap());
+        assert!(elements[0].eq_ignoring_style(elements[3]));
+
+        let invalid = parse_bytes(b"<a x=\"\xFF\"/>").unwrap();
+        let Item::Element(invalid) = &invalid[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert!(invalid.renders_same(elements[0]).is_err());
+    }
+
+    #[test]
+    fn test_new_decl_validation() {
+        for (version, encoding, standalone) in [
+            ("1.0", None, None),
+            ("1.1", Some("ISO-8859-1"), Some("no")),
+            ("1.10", Some("x_y.z"), Some("yes")),
+        ] {
+            let decl = Item::new_decl(version, encoding, standalone).unwrap();
+            let xml = format!("{decl}<a/>");
+            assert!(matches!(parse(&xml).unwrap()[0], Item::Decl(_)));
+        }
+
+        assert_eq!(
+            Other::new_decl("banana", None, None),
+            Err(DeclError::Version(String::from("banana")))
+        );
+        for version in ["", "1", "1.", "2.0", "1.0a", " 1.0"] {
+            assert!(matches!(
+                Item::new_decl(version, None, None),
+                Err(DeclError::Version(_))
+            ));
+        }
+        for encoding in ["", "8BIT", "UTF 8", "utf-8\""] {
+            assert!(matches!(
+                Item::new_decl("1.0", Some(encoding), None),
+                Err(DeclError::Encoding(_))
+            ));
+        }
+        for standalone in ["", "true", "YES"] {
+            assert!(matches!(
+                Item::new_decl("1.0", None, Some(standalone)),
+                Err(DeclError::Standalone(_))
+            ));
+        }
+
+        let unchecked = Item::new_decl_unchecked("banana", None, None);
+        assert_eq!(unchecked.to_string(), r#"<?xml version="banana"?>"#);
+    }
+
+    #[test]
+    fn test_attributes_lazy() {
+        let items =
+            parse_bytes(b"<a one=\"1\" two=\"\xFF\" three=\"a &amp; b\" one=\"4\" four=\"4\"/>")
+                .unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let attributes: Vec<Result<(String, String), Error>> = element.attributes().collect();
+        assert_eq!(attributes.len(), 5);
+        assert_eq!(attributes[0].as_ref().unwrap().1, "1");
+        assert!(matches!(attributes[1], Err(Error::NonDecodable(_))));
+        assert_eq!(attributes[2].as_ref().unwrap().1, "a &amp; b");
+        assert!(matches!(attributes[3], Err(Error::InvalidAttr(_))));
+        assert_eq!(attributes[4].as_ref().unwrap().0, "four");
+
+        let ok: Vec<(String, String)> = element.attributes().filter_map(Result::ok).collect();
+        assert_eq!(ok, element.get_all_attributes().collect::<Vec<_>>());
+
+        let first = element.attributes().next().unwrap().unwrap();
+        assert_eq!(first, (String::from("one"), String::from("1")));
+    }
+
+    #[test]
+    fn test_parse_lossy() {
+        let xml = b"<r\xFFot a=\"\xFE\"><p>\xC3(</p></r\xFFot>";
+
+        let items = parse_lossy(xml).unwrap();
+
+        let Item::Element(root) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(root.get_name().unwrap(), "r\u{FFFD}ot");
+        assert_eq!(root.get_attribute("a").unwrap().unwrap(), "\u{FFFD}");
+        assert_eq!(root.get_text_content(), "\u{FFFD}(");
+        assert_eq!(
+            items_to_string(&items),
+            "<r\u{FFFD}ot a=\"\u{FFFD}\"><p>\u{FFFD}(</p></r\u{FFFD}ot>"
+        );
+
+        let valid = "<a b=\"c\">d</a>";
+        assert_eq!(
+            parse_lossy(valid.as_bytes()).unwrap(),
+            parse(valid).unwrap()
+        );
+
+        assert!(parse_lossy(b"<a>\xFF</b>").is_err());
+    }
+
+    #[test]
+    fn test_matches_name() {
+        let xml = "<a:item><b:item/><item/><a:other/><a/></a:item>";
+
+        let Item::Element(root) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let matching = |pattern: &str| -> Vec<String> {
+            root.find_descendants(&|item| {
+                let Item::Element(element) = item else {
+                    return false;
+                };
+                element.matches_name(pattern)
+            })
+            .map(|item| item.to_string())
+            .collect()
+        };
+
+        assert!(root.matches_name("*"));
+        assert!(root.matches_name("*:item"));
+        assert!(root.matches_name("a:*"));
+        assert!(root.matches_name("a:item"));
+        assert!(!root.matches_name("item"));
+
+        assert_eq!(matching("*").len(), 4);
+        assert_eq!(matching("*:item"), ["<b:item/>", "<item/>"]);
+        assert_eq!(matching("a:*"), ["<a:other/>"]);
+        assert_eq!(matching("*:a"), ["<a/>"]);
+        assert_eq!(matching("item"), ["<item/>"]);
+        assert!(matching("c:*").is_empty());
+    }
+
+    #[test]
+    fn test_get_readable_text() {
+        let xml = r#"<article>
+            <h:title>  Heading  </h:title>
+            <section>
+                <p>First   <em>para</em>graph,<!-- note --> with
+                    a line break.</p>
+                <p/>
+                <p><![CDATA[a < b]]></p>
+            </section>
+            <ul><li>one</li><li>two</li></ul>
+        </article>"#;
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element.get_readable_text(&["*:title", "section", "p", "li"]),
+            "Heading\nFirst paragraph, with a line break.\na < b\none\ntwo"
+        );
+        assert_eq!(
+            element.get_readable_text(&[]),
+            "Heading First paragraph, with a line break. a < b onetwo"
+        );
+
+        let xml =
+            "<div><p>Tom &amp; Jerry &lt;3<![CDATA[ a < b]]></p><p>x&#32;&#10; y&#xA0;z</p></div>";
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element.get_readable_text(&["p"]),
+            "Tom & Jerry <3 a < b\nx y\u{A0}z"
+        );
+    }
+
+    #[test]
+    fn test_new_pi_validation() {
+        let pi = Item::new_pi("php echo 1;").unwrap();
+        assert_eq!(pi.to_string(), "<?php echo 1;?>");
+        assert_eq!(parse("<?php echo 1;?>").unwrap(), [pi]);
+
+        assert!(Other::new_pi("xml-stylesheet").is_ok());
+        assert!(Other::new_pi("_x:y.z-1\tdata").is_ok());
+
+        assert_eq!(
+            Other::new_pi("xml version=\"1.0\""),
+            Err(PIError::ReservedTarget(String::from("xml")))
+        );
+        assert_eq!(
+            Other::new_pi("XmL"),
+            Err(PIError::ReservedTarget(String::from("XmL")))
+        );
+        assert_eq!(Other::new_pi(""), Err(PIError::Target(String::new())));
+        assert_eq!(Other::new_pi(" php"), Err(PIError::Target(String::new())));
+        assert_eq!(
+            Other::new_pi("1php"),
+            Err(PIError::Target(String::from("1php")))
+        );
+        assert_eq!(
+            Other::new_pi("a?b"),
+            Err(PIError::Target(String::from("a?b")))
+        );
+        assert_eq!(Other::new_pi("php ?> <a>"), Err(PIError::Terminator));
+
+        let unchecked = Item::new_pi_unchecked("xml foo");
+        assert_eq!(unchecked.to_string(), "<?xml foo?>");
+    }
+
+    #[test]
+    fn test_set_attribute_preserving() {
+        let xml = "<root>\n  <path\td='M0 0'   class=\"a\"\n        id=b d=\"dup\"></path>\n  <b/>\n</root>";
+
+        let mut items = parse(xml).unwrap();
+        let Item::Element(root) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        let Some(Item::Element(path)) = root.children.get_mut(1) else {
+            panic!("Test data is corrupt.");
+        };
+
+        path.set_attribute_preserving("class", "x & \"y\"").unwrap();
+        path.set_attribute_preserving("d", "M1 1").unwrap();
+        path.set_attribute_preserving("title", "new").unwrap();
+
+        assert_eq!(
+            path.to_string(),
+            "<path\td='M1 1'   class=\"x &amp; &quot;y&quot;\"\n        id=b d=\"dup\" title=\"new\"></path>"
+        );
+        assert_eq!(
+            path.get_attribute("class").unwrap().unwrap(),
+            "x &amp; &quot;y&quot;"
+        );
+
+        let Some(Item::Element(b)) = root.children.get_mut(3) else {
+            panic!("Test data is corrupt.");
+        };
+        b.set_attribute_preserving("c", "'").unwrap();
+        assert_eq!(b.to_string(), "<b c=\"&apos;\"/>");
+
+        let mut items = parse_bytes(b"<a x=\"\xFF\" y=\"1\"/>").unwrap();
+        let Item::Element(invalid) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert!(invalid.set_attribute_preserving("y", "2").is_err());
+        assert_eq!(invalid.get_attribute("y").unwrap().unwrap(), "1");
+
+        let mut items = parse("<a id=b  k=\"v\" flag\n x='1' x='2'/>").unwrap();
+        let Item::Element(malformed) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+        malformed.set_attribute_preserving("id", "x y").unwrap();
+        malformed.set_attribute_preserving("flag", "on").unwrap();
+        malformed.set_attribute_preserving("x", "3").unwrap();
+
+        assert_eq!(
+            malformed.to_string(),
+            "<a id=\"x y\"  k=\"v\" flag=\"on\"\n x='3' x='2'/>"
+        );
+        assert_eq!(malformed.get_attribute("id").unwrap().unwrap(), "x y");
+        assert_eq!(malformed.get_attribute("flag").unwrap().unwrap(), "on");
+    }
+
+    #[test]
+    fn test_is_empty_and_has_only_whitespace() {
+        let xml = "<root><a/><a></a><a> \n&#32;</a><a><![CDATA[ ]]></a><a>x</a><a><b/></a><a><?pi?></a></root>";
+
+        let Item::Element(root) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let flags: Vec<(bool, bool)> = root
+            .find_children("a")
+            .map(|a| (a.is_empty(), a.has_only_whitespace()))
+            .collect();
+
+        assert_eq!(
+            flags,
+            [
+                (true, true),
+                (true, true),
+                (false, true),
+                (false, true),
+                (false, false),
+                (false, false),
+                (false, false),
+            ]
+        );
+
+        let mut element = Element::new("a", false);
+        assert!(element.is_empty());
+        element.children.push(Item::new_text(""));
+        assert!(!element.is_empty());
+        assert!(element.has_only_whitespace());
+    }
+
+    #[test]
+    fn test_remove_attribute_keeps_malformed_attributes() {
+        let (mut items, errors) = parse_lenient(r#"<a x="1" x="2"  k='v' id=b/>"#);
+        assert!(!errors.is_empty());
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert!(!element.remove_attribute("missing"));
+        assert_eq!(element.to_string(), r#"<a x="1" x="2"  k='v' id=b/>"#);
+
+        assert!(element.remove_attribute("k"));
+        assert_eq!(element.to_string(), r#"<a x="1" x="2" id=b/>"#);
+
+        assert!(element.remove_attribute("id"));
+        assert!(element.remove_attribute("x"));
+        assert_eq!(element.to_string(), "<a/>");
+    }
+
+    #[test]
+    fn test_adjacent_text_is_merged() {
+        let options = ParseOptions {
+            cdata_as_text: true,
+            keep_comments: false,
+            keep_processing_instructions: false,
+        };
+
+        let items = parse_with("<a>1<!--x-->2<![CDATA[<3]]>4<?pi?>5</a>", &options).unwrap();
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(element.children.len(), 1);
+        let Item::Text(text) = &element.children[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(text.get_value().unwrap(), "12&lt;345");
+    }
+
+    #[test]
+    fn test_set_attribute_keeps_malformed_attributes() {
+        let mut items = parse(r#"<a id=b k="v"/><a k="v" x="1" x="2" flag/>"#).unwrap();
+        let [Item::Element(unquoted), Item::Element(duplicated)] = &mut items[..] else {
+            panic!("Test data is corrupt.");
+        };
+
+        unquoted.set_attribute("k", "w");
+        assert_eq!(unquoted.to_string(), r#"<a id=b k="w"/>"#);
+
+        unquoted.set_attribute("id", "x y");
+        assert_eq!(unquoted.to_string(), r#"<a id="x y" k="w"/>"#);
+        assert_eq!(unquoted.get_attribute("id").unwrap().unwrap(), "x y");
+
+        duplicated.set_attribute("k", "w");
+        duplicated.set_attribute("new", "1");
+        assert_eq!(
+            duplicated.to_string(),
+            r#"<a k="w" x="1" x="2" flag new="1"/>"#
+        );
+
+        duplicated.set_attribute("x", "3");
+        assert_eq!(
+            duplicated.to_string(),
+            r#"<a k="w" x="3" x="2" flag new="1"/>"#
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_repair_ampersands() {
+        let options = LenientOptions {
+            repair_attributes: true,
+            ..Default::default()
+        };
+        let xml = "<a href=x?a=1&b=2&amp;c=&#51;&#x34;&lt;&nbsp; t=&a;b&#x; u=&;/>";
+
+        let (items, _) = parse_lenient_with(xml, &options);
+
+        let repaired = items_to_string(&items);
+        assert_eq!(
+            repaired,
+            r#"<a href="x?a=1&amp;b=2&amp;c=&#51;&#x34;&lt;&nbsp;" t="&a;b&amp;#x;" u="&amp;;"/>"#
+        );
+
+        let reparsed = parse(&repaired).unwrap();
+        let Item::Element(a) = &reparsed[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(a.get_attribute_cow("u").unwrap().unwrap(), "&;");
+        assert!(a.get_attribute_cow("href").is_err());
+
+        let (items, _) = parse_lenient_with("<a href=x?a=1&b=2/>", &options);
+        let repaired = items_to_string(&items);
+        let Item::Element(a) = &parse(&repaired).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+        assert_eq!(a.get_attribute_cow("href").unwrap().unwrap(), "x?a=1&b=2");
+    }
+
+    #[test]
+    fn test_cursor_replace_after_parent_changed() {
+        let xml = "<a><b/><c/></a>";
+
+        let mut items = parse(xml).unwrap();
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        let mut cursor = element.cursor(1).unwrap();
+        cursor.parent().children.clear();
+
+        assert!(cursor.get().is_none());
+        assert!(cursor.get_mut().is_none());
+        assert!(cursor.replace(vec![Item::new_text("x")]).is_none());
+
+        assert_eq!(element.to_string(), "<a></a>");
+
+        element.graft(Item::new_text("y"));
+        let replaced = element.cursor(0).unwrap().replace(Vec::new());
+
+        assert!(matches!(replaced, Some(Item::Text(_))));
+        assert_eq!(element.to_string(), "<a></a>");
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_to_json_value_skips_indentation() {
+        use serde_json::json;
+
+        let xml =
+            "<book>\n  <title> Rust </title>\n  <tag>a</tag>\n  <note>x <b>y</b> z</note>\n</book>";
+
+        let Item::Element(element) = &parse(xml).unwrap()[0] else {
+            panic!("Test data is corrupt.");
+        };
+
+        assert_eq!(
+            element.to_json_value(),
+            json!({
+                "title": [{ "#text": " Rust " }],
+                "tag": [{ "#text": "a" }],
+                "note": [{ "#text": "x  z", "b": [{ "#text": "y" }] }],
+            })
+        );
+    }
 }